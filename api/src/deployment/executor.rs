@@ -0,0 +1,57 @@
+use lib::app_config::ChartV1_0_0;
+use std::path::PathBuf;
+use std::process::Command;
+use tracing::debug;
+
+/// Releases a single chart of a deployment's plan. Implementations decide how, e.g. by
+/// running Helm against a cluster, so the service can be exercised without one.
+pub trait ChartExecutor: Send + Sync {
+    /// Releases `chart` for the application into the environment. Returns the reason the
+    /// release failed, if it did.
+    fn release(&self, app: &str, environment: &str, chart: &ChartV1_0_0) -> Result<(), String>;
+}
+
+/// Releases charts with `helm upgrade --install`. Each environment is the kubeconfig context
+/// of the same name, and each chart is released under its own name into its namespace.
+#[derive(Debug, Clone)]
+pub struct HelmExecutor {
+    pub helm: PathBuf,
+}
+
+impl Default for HelmExecutor {
+    fn default() -> Self {
+        HelmExecutor {
+            helm: PathBuf::from("helm"),
+        }
+    }
+}
+
+impl HelmExecutor {
+    fn command(&self, environment: &str, chart: &ChartV1_0_0) -> Command {
+        let mut command = Command::new(&self.helm);
+        command
+            .args(["upgrade", "--install", &chart.name, &chart.url])
+            .args(["--namespace", &chart.namespace, "--create-namespace"])
+            .args(["--kube-context", environment, "--wait"]);
+        command
+    }
+}
+
+impl ChartExecutor for HelmExecutor {
+    fn release(&self, app: &str, environment: &str, chart: &ChartV1_0_0) -> Result<(), String> {
+        debug!(app, environment, chart = %chart.name, "Running helm");
+        let output = self
+            .command(environment, chart)
+            .output()
+            .map_err(|e| format!("Unable to run {}: {}", self.helm.display(), e))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!(
+            "helm exited with {}: {}",
+            output.status,
+            stderr.trim()
+        ))
+    }
+}
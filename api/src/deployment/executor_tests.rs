@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{ChartExecutor, HelmExecutor};
+    use lib::app_config::ChartV1_0_0;
+    use std::path::PathBuf;
+
+    fn chart() -> ChartV1_0_0 {
+        ChartV1_0_0 {
+            name: "web".to_string(),
+            url: "oci://charts/web".to_string(),
+            namespace: "default".to_string(),
+        }
+    }
+
+    fn helm(path: &str) -> HelmExecutor {
+        HelmExecutor {
+            helm: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn test_release_succeeds_when_helm_succeeds() {
+        assert_eq!(helm("true").release("my-app", "staging", &chart()), Ok(()));
+    }
+
+    #[test]
+    fn test_release_fails_when_helm_fails() {
+        let error = helm("false")
+            .release("my-app", "staging", &chart())
+            .unwrap_err();
+        assert!(error.starts_with("helm exited with"));
+    }
+
+    #[test]
+    fn test_release_fails_without_helm() {
+        let error = helm("/nonexistent/helm")
+            .release("my-app", "staging", &chart())
+            .unwrap_err();
+        assert!(error.starts_with("Unable to run /nonexistent/helm"));
+    }
+}
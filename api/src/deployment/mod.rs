@@ -0,0 +1,16 @@
+mod executor;
+mod model;
+mod service;
+mod store;
+
+#[cfg(test)]
+mod executor_tests;
+#[cfg(test)]
+mod model_tests;
+#[cfg(test)]
+mod service_tests;
+
+pub use self::executor::{ChartExecutor, HelmExecutor};
+pub use self::model::*;
+pub use self::service::DeploymentService;
+pub use self::store::InMemoryDeploymentStore;
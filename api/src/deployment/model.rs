@@ -0,0 +1,148 @@
+use lib::app_config::ChartV1_0_0;
+use serde::Serialize;
+use std::fmt;
+use thiserror::Error;
+
+pub type DeploymentId = u64;
+
+/// The lifecycle of a deployment: queued -> running -> succeeded, failed or cancelled.
+/// A queued deployment may also be cancelled before it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeploymentState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl DeploymentState {
+    /// Whether the deployment has finished and can't change anymore.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            DeploymentState::Succeeded | DeploymentState::Failed | DeploymentState::Cancelled
+        )
+    }
+
+    pub fn can_transition_to(&self, next: DeploymentState) -> bool {
+        matches!(
+            (self, next),
+            (DeploymentState::Queued, DeploymentState::Running)
+                | (DeploymentState::Queued, DeploymentState::Cancelled)
+                | (DeploymentState::Running, DeploymentState::Succeeded)
+                | (DeploymentState::Running, DeploymentState::Failed)
+                | (DeploymentState::Running, DeploymentState::Cancelled)
+        )
+    }
+}
+
+impl fmt::Display for DeploymentState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DeploymentState::Queued => "queued",
+            DeploymentState::Running => "running",
+            DeploymentState::Succeeded => "succeeded",
+            DeploymentState::Failed => "failed",
+            DeploymentState::Cancelled => "cancelled",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The status of releasing one chart of the plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepState {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    /// Never ran because the deployment failed or was cancelled first.
+    Skipped,
+}
+
+/// One chart release of a deployment.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentStep {
+    pub chart: ChartV1_0_0,
+    pub state: StepState,
+    /// Why the release failed, if it did.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Deployment {
+    pub id: DeploymentId,
+    pub app: String,
+    pub environment: String,
+    pub state: DeploymentState,
+    /// The app's charts, released in order.
+    pub steps: Vec<DeploymentStep>,
+    /// Set by a cancel request while running, honoured before the next step.
+    pub cancel_requested: bool,
+}
+
+impl Deployment {
+    pub fn new(id: DeploymentId, app: &str, environment: &str, charts: &[ChartV1_0_0]) -> Self {
+        Deployment {
+            id,
+            app: app.to_string(),
+            environment: environment.to_string(),
+            state: DeploymentState::Queued,
+            steps: charts
+                .iter()
+                .map(|chart| DeploymentStep {
+                    chart: chart.clone(),
+                    state: StepState::Pending,
+                    error: None,
+                })
+                .collect(),
+            cancel_requested: false,
+        }
+    }
+
+    /// Moves to `next`, rejecting transitions the lifecycle doesn't allow. Steps that didn't
+    /// run are skipped once the deployment finishes.
+    pub fn transition(&mut self, next: DeploymentState) -> Result<(), DeploymentError> {
+        if !self.state.can_transition_to(next) {
+            return Err(DeploymentError::InvalidTransition {
+                id: self.id,
+                from: self.state,
+                to: next,
+            });
+        }
+        self.state = next;
+        if next.is_terminal() {
+            for step in &mut self.steps {
+                if step.state == StepState::Pending {
+                    step.state = StepState::Skipped;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DeploymentError {
+    #[error("Deployment {0} not found")]
+    NotFound(DeploymentId),
+    #[error("Deployment {id} can't go from {from} to {to}")]
+    InvalidTransition {
+        id: DeploymentId,
+        from: DeploymentState,
+        to: DeploymentState,
+    },
+    #[error("{app} already has deployment {id} in progress in {environment}")]
+    AlreadyActive {
+        id: DeploymentId,
+        app: String,
+        environment: String,
+    },
+    #[error("Deployment {0} is releasing its last chart and can no longer be cancelled")]
+    CancelTooLate(DeploymentId),
+    #[error("{0} has no charts to deploy")]
+    EmptyPlan(String),
+}
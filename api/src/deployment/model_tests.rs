@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{Deployment, DeploymentError, DeploymentState, StepState};
+    use lib::app_config::ChartV1_0_0;
+
+    fn chart(name: &str) -> ChartV1_0_0 {
+        ChartV1_0_0 {
+            name: name.to_string(),
+            url: format!("oci://charts/{}", name),
+            namespace: "default".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_new_deployment_is_queued_with_pending_steps() {
+        let deployment = Deployment::new(1, "my-app", "staging", &[chart("web"), chart("db")]);
+        assert_eq!(deployment.state, DeploymentState::Queued);
+        assert_eq!(deployment.steps.len(), 2);
+        assert!(
+            deployment
+                .steps
+                .iter()
+                .all(|s| s.state == StepState::Pending)
+        );
+    }
+
+    #[test]
+    fn test_transitions() {
+        use DeploymentState::*;
+        assert!(Queued.can_transition_to(Running));
+        assert!(Queued.can_transition_to(Cancelled));
+        assert!(Running.can_transition_to(Succeeded));
+        assert!(Running.can_transition_to(Failed));
+        assert!(Running.can_transition_to(Cancelled));
+        assert!(!Queued.can_transition_to(Succeeded));
+        assert!(!Succeeded.can_transition_to(Running));
+        assert!(!Cancelled.can_transition_to(Cancelled));
+    }
+
+    #[test]
+    fn test_finishing_skips_pending_steps() {
+        let mut deployment = Deployment::new(1, "my-app", "staging", &[chart("web")]);
+        deployment.transition(DeploymentState::Cancelled).unwrap();
+        assert_eq!(deployment.steps[0].state, StepState::Skipped);
+    }
+
+    #[test]
+    fn test_invalid_transition_is_rejected() {
+        let mut deployment = Deployment::new(7, "my-app", "staging", &[chart("web")]);
+        let error = deployment
+            .transition(DeploymentState::Succeeded)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            DeploymentError::InvalidTransition {
+                id: 7,
+                from: DeploymentState::Queued,
+                to: DeploymentState::Succeeded,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Deployment 7 can't go from queued to succeeded"
+        );
+        assert_eq!(deployment.state, DeploymentState::Queued);
+    }
+}
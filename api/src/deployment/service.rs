@@ -0,0 +1,128 @@
+use crate::deployment::executor::ChartExecutor;
+use crate::deployment::model::{
+    Deployment, DeploymentError, DeploymentId, DeploymentState, StepState,
+};
+use crate::deployment::store::InMemoryDeploymentStore;
+use lib::app_config::AppConfigV1_0_0;
+use tracing::{info, info_span, warn};
+
+/// Starts, runs, inspects and cancels deployments, releasing charts through `E`.
+pub struct DeploymentService<E: ChartExecutor> {
+    store: InMemoryDeploymentStore,
+    executor: E,
+}
+
+impl<E: ChartExecutor> DeploymentService<E> {
+    pub fn new(executor: E) -> Self {
+        DeploymentService {
+            store: InMemoryDeploymentStore::default(),
+            executor,
+        }
+    }
+
+    /// Queues a deployment of the app's charts to `environment`. Only one deployment per app
+    /// and environment may be unfinished at a time.
+    pub fn start(
+        &self,
+        config: &AppConfigV1_0_0,
+        environment: &str,
+    ) -> Result<Deployment, DeploymentError> {
+        if config.app.charts.is_empty() {
+            return Err(DeploymentError::EmptyPlan(config.app.name.clone()));
+        }
+        let deployment = self
+            .store
+            .create(&config.app.name, environment, &config.app.charts)?;
+        info!(
+            id = deployment.id,
+            app = %deployment.app,
+            environment = %deployment.environment,
+            "Queued deployment"
+        );
+        Ok(deployment)
+    }
+
+    /// Releases the charts of a queued deployment in order. A failed release fails the
+    /// deployment and skips the remaining charts; a cancel request stops it before the next
+    /// chart. Blocks until the deployment finishes, so callers run it on its own thread.
+    pub fn run(&self, id: DeploymentId) -> Result<Deployment, DeploymentError> {
+        let _span = info_span!("deployment", id).entered();
+        let (_, deployment) = self
+            .store
+            .update(id, |d| d.transition(DeploymentState::Running))?;
+
+        for index in 0..deployment.steps.len() {
+            let (cancelled, deployment) = self.store.update(id, |d| {
+                if d.cancel_requested {
+                    d.transition(DeploymentState::Cancelled)?;
+                    return Ok(true);
+                }
+                d.steps[index].state = StepState::Running;
+                Ok(false)
+            })?;
+            if cancelled {
+                info!("Cancelled deployment");
+                return Ok(deployment);
+            }
+
+            let chart = &deployment.steps[index].chart;
+            let result = self
+                .executor
+                .release(&deployment.app, &deployment.environment, chart);
+            let (_, deployment) = self.store.update(id, |d| {
+                let step = &mut d.steps[index];
+                match result {
+                    Ok(()) => {
+                        step.state = StepState::Succeeded;
+                        Ok(())
+                    }
+                    Err(error) => {
+                        warn!(chart = %step.chart.name, %error, "Chart release failed");
+                        step.state = StepState::Failed;
+                        step.error = Some(error);
+                        d.transition(DeploymentState::Failed)
+                    }
+                }
+            })?;
+            if deployment.state == DeploymentState::Failed {
+                return Ok(deployment);
+            }
+        }
+
+        let (_, deployment) = self
+            .store
+            .update(id, |d| d.transition(DeploymentState::Succeeded))?;
+        info!("Deployment succeeded");
+        Ok(deployment)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    pub fn get(&self, id: DeploymentId) -> Result<Deployment, DeploymentError> {
+        self.store.get(id)
+    }
+
+    pub fn list(&self) -> Vec<Deployment> {
+        self.store.list()
+    }
+
+    /// Cancels a queued deployment right away. A running deployment stops before its next
+    /// chart; the chart being released finishes first. Once the last chart is being released
+    /// there is nothing left to stop, so the cancel is refused.
+    pub fn cancel(&self, id: DeploymentId) -> Result<Deployment, DeploymentError> {
+        let (_, deployment) = self.store.update(id, |d| match d.state {
+            DeploymentState::Running => {
+                if !d.steps.iter().any(|s| s.state == StepState::Pending) {
+                    return Err(DeploymentError::CancelTooLate(d.id));
+                }
+                d.cancel_requested = true;
+                Ok(())
+            }
+            _ => d.transition(DeploymentState::Cancelled),
+        })?;
+        Ok(deployment)
+    }
+}
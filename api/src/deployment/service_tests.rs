@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        ChartExecutor, DeploymentError, DeploymentService, DeploymentState, StepState,
+    };
+    use lib::app_config::{
+        AppConfigV1_0_0, ChartV1_0_0, deserialize_app_config, write_app_config_template,
+    };
+    use std::sync::Mutex;
+    use std::sync::mpsc::{Receiver, Sender, channel};
+    use std::thread;
+
+    fn app_config(charts: &[&str]) -> AppConfigV1_0_0 {
+        let mut config = deserialize_app_config(&write_app_config_template(
+            "my-app",
+            "https://deplio",
+            "acme",
+        ))
+        .expect("template should deserialize");
+        config.app.charts = charts
+            .iter()
+            .map(|name| ChartV1_0_0 {
+                name: name.to_string(),
+                url: format!("oci://charts/{}", name),
+                namespace: "default".to_string(),
+            })
+            .collect();
+        config
+    }
+
+    /// Records released charts and fails those named in `failing`.
+    #[derive(Default)]
+    struct RecordingExecutor {
+        failing: Vec<String>,
+        released: Mutex<Vec<String>>,
+    }
+
+    impl ChartExecutor for RecordingExecutor {
+        fn release(
+            &self,
+            _app: &str,
+            _environment: &str,
+            chart: &ChartV1_0_0,
+        ) -> Result<(), String> {
+            self.released.lock().unwrap().push(chart.name.clone());
+            if self.failing.contains(&chart.name) {
+                return Err(format!("{} failed", chart.name));
+            }
+            Ok(())
+        }
+    }
+
+    /// Signals every release and waits to be told to finish it.
+    struct BlockingExecutor {
+        started: Mutex<Sender<()>>,
+        resume: Mutex<Receiver<()>>,
+    }
+
+    impl ChartExecutor for BlockingExecutor {
+        fn release(
+            &self,
+            _app: &str,
+            _environment: &str,
+            _chart: &ChartV1_0_0,
+        ) -> Result<(), String> {
+            self.started.lock().unwrap().send(()).unwrap();
+            self.resume.lock().unwrap().recv().unwrap();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_run_releases_every_chart_in_order() {
+        let service = DeploymentService::new(RecordingExecutor::default());
+        let deployment = service
+            .start(&app_config(&["db", "web"]), "staging")
+            .unwrap();
+        assert_eq!(deployment.state, DeploymentState::Queued);
+
+        let deployment = service.run(deployment.id).unwrap();
+        assert_eq!(deployment.state, DeploymentState::Succeeded);
+        assert!(
+            deployment
+                .steps
+                .iter()
+                .all(|s| s.state == StepState::Succeeded)
+        );
+        assert_eq!(
+            service.get(deployment.id).unwrap().state,
+            DeploymentState::Succeeded
+        );
+    }
+
+    #[test]
+    fn test_failed_release_fails_deployment_and_skips_the_rest() {
+        let service = DeploymentService::new(RecordingExecutor {
+            failing: vec!["db".to_string()],
+            ..Default::default()
+        });
+        let id = service
+            .start(&app_config(&["db", "web"]), "staging")
+            .unwrap()
+            .id;
+
+        let deployment = service.run(id).unwrap();
+        assert_eq!(deployment.state, DeploymentState::Failed);
+        assert_eq!(deployment.steps[0].state, StepState::Failed);
+        assert_eq!(deployment.steps[0].error.as_deref(), Some("db failed"));
+        assert_eq!(deployment.steps[1].state, StepState::Skipped);
+        assert_eq!(*service.executor().released.lock().unwrap(), vec!["db"]);
+    }
+
+    #[test]
+    fn test_start_rejects_second_active_deployment() {
+        let service = DeploymentService::new(RecordingExecutor::default());
+        let config = app_config(&["web"]);
+        let first = service.start(&config, "staging").unwrap();
+
+        assert_eq!(
+            service.start(&config, "staging").unwrap_err(),
+            DeploymentError::AlreadyActive {
+                id: first.id,
+                app: "my-app".to_string(),
+                environment: "staging".to_string(),
+            }
+        );
+        assert!(service.start(&config, "production").is_ok());
+
+        service.run(first.id).unwrap();
+        assert!(service.start(&config, "staging").is_ok());
+    }
+
+    #[test]
+    fn test_start_rejects_empty_plan() {
+        let service = DeploymentService::new(RecordingExecutor::default());
+        assert_eq!(
+            service.start(&app_config(&[]), "staging").unwrap_err(),
+            DeploymentError::EmptyPlan("my-app".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cancel_queued_deployment() {
+        let service = DeploymentService::new(RecordingExecutor::default());
+        let id = service.start(&app_config(&["web"]), "staging").unwrap().id;
+
+        let deployment = service.cancel(id).unwrap();
+        assert_eq!(deployment.state, DeploymentState::Cancelled);
+        assert_eq!(deployment.steps[0].state, StepState::Skipped);
+        assert!(matches!(
+            service.run(id),
+            Err(DeploymentError::InvalidTransition { .. })
+        ));
+        assert!(matches!(
+            service.cancel(id),
+            Err(DeploymentError::InvalidTransition { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cancel_running_deployment_stops_before_next_chart() {
+        let (started_tx, started_rx) = channel();
+        let (resume_tx, resume_rx) = channel();
+        let service = DeploymentService::new(BlockingExecutor {
+            started: Mutex::new(started_tx),
+            resume: Mutex::new(resume_rx),
+        });
+        let id = service
+            .start(&app_config(&["db", "web"]), "staging")
+            .unwrap()
+            .id;
+
+        let deployment = thread::scope(|scope| {
+            let run = scope.spawn(|| service.run(id));
+            started_rx.recv().unwrap();
+            let running = service.cancel(id).unwrap();
+            assert_eq!(running.state, DeploymentState::Running);
+            assert_eq!(running.steps[0].state, StepState::Running);
+            resume_tx.send(()).unwrap();
+            run.join().unwrap().unwrap()
+        });
+
+        assert_eq!(deployment.state, DeploymentState::Cancelled);
+        assert_eq!(deployment.steps[0].state, StepState::Succeeded);
+        assert_eq!(deployment.steps[1].state, StepState::Skipped);
+    }
+
+    #[test]
+    fn test_cancel_refused_while_releasing_last_chart() {
+        let (started_tx, started_rx) = channel();
+        let (resume_tx, resume_rx) = channel();
+        let service = DeploymentService::new(BlockingExecutor {
+            started: Mutex::new(started_tx),
+            resume: Mutex::new(resume_rx),
+        });
+        let id = service.start(&app_config(&["web"]), "staging").unwrap().id;
+
+        let deployment = thread::scope(|scope| {
+            let run = scope.spawn(|| service.run(id));
+            started_rx.recv().unwrap();
+            assert_eq!(
+                service.cancel(id).unwrap_err(),
+                DeploymentError::CancelTooLate(id)
+            );
+            resume_tx.send(()).unwrap();
+            run.join().unwrap().unwrap()
+        });
+
+        assert_eq!(deployment.state, DeploymentState::Succeeded);
+        assert!(!deployment.cancel_requested);
+    }
+
+    #[test]
+    fn test_get_unknown_deployment() {
+        let service = DeploymentService::new(RecordingExecutor::default());
+        assert_eq!(service.get(42).unwrap_err(), DeploymentError::NotFound(42));
+        assert_eq!(
+            service.cancel(42).unwrap_err().to_string(),
+            "Deployment 42 not found"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::deployment::model::{Deployment, DeploymentError, DeploymentId};
+use lib::app_config::ChartV1_0_0;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Keeps deployments in memory. Deployments are lost when the service restarts.
+#[derive(Debug, Default)]
+pub struct InMemoryDeploymentStore {
+    inner: Mutex<StoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct StoreInner {
+    next_id: DeploymentId,
+    deployments: BTreeMap<DeploymentId, Deployment>,
+}
+
+impl InMemoryDeploymentStore {
+    /// Stores a new queued deployment, unless the app already has an unfinished deployment
+    /// in the environment.
+    pub fn create(
+        &self,
+        app: &str,
+        environment: &str,
+        charts: &[ChartV1_0_0],
+    ) -> Result<Deployment, DeploymentError> {
+        let mut inner = self.inner.lock().expect("deployment store lock poisoned");
+        if let Some(active) = inner
+            .deployments
+            .values()
+            .find(|d| d.app == app && d.environment == environment && !d.state.is_terminal())
+        {
+            return Err(DeploymentError::AlreadyActive {
+                id: active.id,
+                app: app.to_string(),
+                environment: environment.to_string(),
+            });
+        }
+        inner.next_id += 1;
+        let deployment = Deployment::new(inner.next_id, app, environment, charts);
+        inner.deployments.insert(deployment.id, deployment.clone());
+        Ok(deployment)
+    }
+
+    pub fn get(&self, id: DeploymentId) -> Result<Deployment, DeploymentError> {
+        let inner = self.inner.lock().expect("deployment store lock poisoned");
+        inner
+            .deployments
+            .get(&id)
+            .cloned()
+            .ok_or(DeploymentError::NotFound(id))
+    }
+
+    pub fn list(&self) -> Vec<Deployment> {
+        let inner = self.inner.lock().expect("deployment store lock poisoned");
+        inner.deployments.values().cloned().collect()
+    }
+
+    /// Applies `change` to a deployment atomically, returning the updated deployment.
+    pub fn update<T>(
+        &self,
+        id: DeploymentId,
+        change: impl FnOnce(&mut Deployment) -> Result<T, DeploymentError>,
+    ) -> Result<(T, Deployment), DeploymentError> {
+        let mut inner = self.inner.lock().expect("deployment store lock poisoned");
+        let deployment = inner
+            .deployments
+            .get_mut(&id)
+            .ok_or(DeploymentError::NotFound(id))?;
+        let result = change(deployment)?;
+        Ok((result, deployment.clone()))
+    }
+}
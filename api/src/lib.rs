@@ -0,0 +1,5 @@
+pub mod deployment;
+pub mod routes;
+
+#[cfg(test)]
+mod routes_tests;
@@ -1,3 +1,35 @@
-fn main() {
-    println!("Hello, world!");
+use api::deployment::{DeploymentService, HelmExecutor};
+use api::routes;
+use lib::logging::{self, LevelFilter, LogSettings};
+use std::env;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing::{error, info};
+
+/// The address to listen on, `127.0.0.1:8080` by default.
+const ADDR_VAR: &str = "DEPLIO_SERVICE_ADDR";
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = LogSettings::from_env(LevelFilter::INFO).and_then(|s| logging::init(&s)) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    let addr = env::var(ADDR_VAR).unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Unable to listen on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+
+    let service = Arc::new(DeploymentService::new(HelmExecutor::default()));
+    info!("Listening on {}", addr);
+    if let Err(e) = axum::serve(listener, routes::router(service)).await {
+        error!("Server failed: {}", e);
+        std::process::exit(1);
+    }
 }
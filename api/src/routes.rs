@@ -0,0 +1,105 @@
+use crate::deployment::{
+    ChartExecutor, Deployment, DeploymentError, DeploymentId, DeploymentService,
+};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use lib::app_config::AppConfigV1_0_0;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+/// The body of `POST /deployments`: the app config of the project to deploy.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StartRequest {
+    pub environment: String,
+    pub config: AppConfigV1_0_0,
+}
+
+/// The deployment endpoints:
+///
+/// - `POST /deployments` starts a deployment and runs it in the background
+/// - `GET /deployments` lists deployments
+/// - `GET /deployments/{id}` inspects a deployment and the status of each chart release
+/// - `POST /deployments/{id}/cancel` cancels a deployment
+pub fn router<E: ChartExecutor + 'static>(service: Arc<DeploymentService<E>>) -> Router {
+    Router::new()
+        .route("/deployments", post(start::<E>).get(list::<E>))
+        .route("/deployments/{id}", get(inspect::<E>))
+        .route("/deployments/{id}/cancel", post(cancel::<E>))
+        .with_state(service)
+}
+
+async fn start<E: ChartExecutor + 'static>(
+    State(service): State<Arc<DeploymentService<E>>>,
+    Json(request): Json<StartRequest>,
+) -> Result<(StatusCode, Json<Deployment>), ApiError> {
+    let deployment = service.start(&request.config, &request.environment)?;
+    let id = deployment.id;
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = service.run(id) {
+            error!(id, "Unable to run deployment: {}", e);
+        }
+    });
+    Ok((StatusCode::ACCEPTED, Json(deployment)))
+}
+
+async fn list<E: ChartExecutor + 'static>(
+    State(service): State<Arc<DeploymentService<E>>>,
+) -> Json<Vec<Deployment>> {
+    Json(service.list())
+}
+
+async fn inspect<E: ChartExecutor + 'static>(
+    State(service): State<Arc<DeploymentService<E>>>,
+    Path(id): Path<DeploymentId>,
+) -> Result<Json<Deployment>, ApiError> {
+    Ok(Json(service.get(id)?))
+}
+
+async fn cancel<E: ChartExecutor + 'static>(
+    State(service): State<Arc<DeploymentService<E>>>,
+    Path(id): Path<DeploymentId>,
+) -> Result<Json<Deployment>, ApiError> {
+    Ok(Json(service.cancel(id)?))
+}
+
+/// A [`DeploymentError`] as an HTTP response: `{"error": {"message": ...}}`.
+#[derive(Debug)]
+pub struct ApiError(DeploymentError);
+
+impl From<DeploymentError> for ApiError {
+    fn from(error: DeploymentError) -> Self {
+        ApiError(error)
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorOutput {
+    error: ErrorDetails,
+}
+
+#[derive(Serialize)]
+struct ErrorDetails {
+    message: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            DeploymentError::NotFound(_) => StatusCode::NOT_FOUND,
+            DeploymentError::InvalidTransition { .. }
+            | DeploymentError::AlreadyActive { .. }
+            | DeploymentError::CancelTooLate(_) => StatusCode::CONFLICT,
+            DeploymentError::EmptyPlan(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        let body = ErrorOutput {
+            error: ErrorDetails {
+                message: self.0.to_string(),
+            },
+        };
+        (status, Json(body)).into_response()
+    }
+}
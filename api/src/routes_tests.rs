@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use super::super::routes::{StartRequest, router};
+    use crate::deployment::{ChartExecutor, DeploymentService};
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use lib::app_config::{ChartV1_0_0, deserialize_app_config, write_app_config_template};
+    use serde_json::Value;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    struct SucceedingExecutor;
+
+    impl ChartExecutor for SucceedingExecutor {
+        fn release(
+            &self,
+            _app: &str,
+            _environment: &str,
+            _chart: &ChartV1_0_0,
+        ) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    fn app() -> Router {
+        router(Arc::new(DeploymentService::new(SucceedingExecutor)))
+    }
+
+    fn start_body(charts: &[&str]) -> String {
+        let mut config = deserialize_app_config(&write_app_config_template(
+            "my-app",
+            "https://deplio",
+            "acme",
+        ))
+        .expect("template should deserialize");
+        config.app.charts = charts
+            .iter()
+            .map(|name| ChartV1_0_0 {
+                name: name.to_string(),
+                url: format!("oci://charts/{}", name),
+                namespace: "default".to_string(),
+            })
+            .collect();
+        serde_json::to_string(&StartRequest {
+            environment: "staging".to_string(),
+            config,
+        })
+        .unwrap()
+    }
+
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<String>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map(Body::from).unwrap_or_else(Body::empty))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_start_runs_deployment_in_background() {
+        let app = app();
+        let (status, deployment) =
+            send(&app, "POST", "/deployments", Some(start_body(&["web"]))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(deployment["state"], "queued");
+        assert_eq!(deployment["steps"][0]["chart"]["name"], "web");
+
+        let uri = format!("/deployments/{}", deployment["id"]);
+        let mut deployment = Value::Null;
+        for _ in 0..100 {
+            let (status, inspected) = send(&app, "GET", &uri, None).await;
+            assert_eq!(status, StatusCode::OK);
+            deployment = inspected;
+            if deployment["state"] == "succeeded" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(deployment["state"], "succeeded");
+        assert_eq!(deployment["steps"][0]["state"], "succeeded");
+
+        let (_, deployments) = send(&app, "GET", "/deployments", None).await;
+        assert_eq!(deployments.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_start_rejects_empty_plan() {
+        let (status, body) = send(&app(), "POST", "/deployments", Some(start_body(&[]))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["message"], "my-app has no charts to deploy");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_deployment_is_not_found() {
+        let app = app();
+        let (status, body) = send(&app, "GET", "/deployments/42", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["message"], "Deployment 42 not found");
+
+        let (status, _) = send(&app, "POST", "/deployments/42/cancel", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
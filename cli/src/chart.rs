@@ -0,0 +1,485 @@
+use crate::error::CliError;
+use crate::output::{self, OutputFormat, Render};
+use crate::parser::ChartCommands;
+use crate::project::AppConfigFile;
+use crate::validation::validate_dns_label;
+use lib::app_config::ChartV1_0_0;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, InlineTable, TableLike, value};
+
+pub fn handle_command(
+    command: &ChartCommands,
+    project_dir: &Path,
+    output: OutputFormat,
+) -> Result<(), CliError> {
+    let file = AppConfigFile::load(project_dir)?;
+
+    match command {
+        ChartCommands::List => output::print(
+            output,
+            &ChartList {
+                charts: file.config.app.charts.clone(),
+            },
+        ),
+        command => output::print(output, &edit_charts(&file, command)?),
+    }
+}
+
+/// Applies `chart add`, `update` or `remove` to the project's app config and saves it.
+fn edit_charts(file: &AppConfigFile, command: &ChartCommands) -> Result<ChartChange, CliError> {
+    let mut document = file.document();
+    let (action, name) = match command {
+        ChartCommands::List => unreachable!("listing doesn't edit charts"),
+        ChartCommands::Add {
+            name,
+            url,
+            namespace,
+        } => {
+            add_chart(
+                &mut document,
+                &file.config.app.charts,
+                &ChartV1_0_0 {
+                    name: name.clone(),
+                    url: url.clone(),
+                    namespace: namespace.clone(),
+                },
+            )?;
+            (ChartAction::Added, name)
+        }
+        ChartCommands::Update {
+            name,
+            url,
+            namespace,
+        } => {
+            update_chart(&mut document, name, url.as_deref(), namespace.as_deref())?;
+            (ChartAction::Updated, name)
+        }
+        ChartCommands::Remove { name } => {
+            remove_chart(&mut document, name)?;
+            (ChartAction::Removed, name)
+        }
+    };
+
+    let saved = file.save(&document)?;
+    // a removed chart is reported as it was before removal
+    let charts = match action {
+        ChartAction::Removed => &file.config.app.charts,
+        _ => &saved.app.charts,
+    };
+    let chart = charts
+        .iter()
+        .find(|chart| chart.name == *name)
+        .cloned()
+        .ok_or_else(|| chart_not_found(name))?;
+    Ok(ChartChange {
+        action,
+        chart,
+        path: file.path.clone(),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartAction {
+    Added,
+    Updated,
+    Removed,
+}
+
+/// The outcome of `deplio chart add`, `update` and `remove`. Prints nothing as text.
+#[derive(Debug, Serialize)]
+pub struct ChartChange {
+    pub action: ChartAction,
+    /// The chart as written, or as it was before being removed.
+    pub chart: ChartV1_0_0,
+    pub path: PathBuf,
+}
+
+impl Render for ChartChange {
+    fn render_text(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// The charts of the application, as printed by `deplio chart list`.
+#[derive(Debug, Serialize)]
+pub struct ChartList {
+    pub charts: Vec<ChartV1_0_0>,
+}
+
+impl Render for ChartList {
+    fn render_text(&self) -> Vec<String> {
+        list_charts(&self.charts)
+    }
+}
+
+/// Formats the charts as an aligned table.
+pub fn list_charts(charts: &[ChartV1_0_0]) -> Vec<String> {
+    if charts.is_empty() {
+        return vec!["No charts configured".to_string()];
+    }
+    let name_width = charts
+        .iter()
+        .map(|c| c.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let namespace_width = charts
+        .iter()
+        .map(|c| c.namespace.len())
+        .max()
+        .unwrap_or(0)
+        .max(9);
+
+    let mut lines = vec![format!(
+        "{:name_width$}  {:namespace_width$}  URL",
+        "NAME", "NAMESPACE"
+    )];
+    for chart in charts {
+        lines.push(format!(
+            "{:name_width$}  {:namespace_width$}  {}",
+            chart.name, chart.namespace, chart.url
+        ));
+    }
+    lines
+}
+
+/// Appends a chart to `app.charts`, rejecting duplicate names and invalid values.
+pub fn add_chart(
+    document: &mut DocumentMut,
+    existing: &[ChartV1_0_0],
+    chart: &ChartV1_0_0,
+) -> Result<(), CliError> {
+    validate_chart_value("name", &chart.name)?;
+    validate_chart_value("namespace", &chart.namespace)?;
+    if chart.url.trim().is_empty() {
+        return Err(CliError::usage("Invalid chart url: can't be empty"));
+    }
+    if existing.iter().any(|c| c.name == chart.name) {
+        return Err(
+            CliError::usage(format!("A chart named {} already exists", chart.name))
+                .with_suggestion(format!(
+                    "use `deplio chart update {}` to change it",
+                    chart.name
+                )),
+        );
+    }
+
+    let mut fields = InlineTable::new();
+    fields.insert("name", chart.name.as_str().into());
+    fields.insert("url", chart.url.as_str().into());
+    fields.insert("namespace", chart.namespace.as_str().into());
+
+    match charts_item(document)? {
+        ChartsItem::Array(array) => push_inline(array, fields),
+        ChartsItem::Tables(tables) => tables.push(fields.into_table()),
+    }
+    Ok(())
+}
+
+/// Changes the url and/or namespace of an existing chart.
+pub fn update_chart(
+    document: &mut DocumentMut,
+    name: &str,
+    url: Option<&str>,
+    namespace: Option<&str>,
+) -> Result<(), CliError> {
+    if let Some(namespace) = namespace {
+        validate_chart_value("namespace", namespace)?;
+    }
+    if url.is_some_and(|url| url.trim().is_empty()) {
+        return Err(CliError::usage("Invalid chart url: can't be empty"));
+    }
+    if url.is_none() && namespace.is_none() {
+        return Err(
+            CliError::usage("Nothing to update").with_suggestion("pass --url and/or --namespace")
+        );
+    }
+
+    let chart = find_chart(charts_item(document)?, name)?;
+    if let Some(url) = url {
+        chart.insert("url", value(url));
+    }
+    if let Some(namespace) = namespace {
+        chart.insert("namespace", value(namespace));
+    }
+    Ok(())
+}
+
+/// Removes a chart from `app.charts`.
+pub fn remove_chart(document: &mut DocumentMut, name: &str) -> Result<(), CliError> {
+    match charts_item(document)? {
+        ChartsItem::Array(array) => {
+            let index = array
+                .iter()
+                .position(|chart| chart_name(chart.as_inline_table()) == Some(name))
+                .ok_or_else(|| chart_not_found(name))?;
+            array.remove(index);
+            if array.is_empty() {
+                // back to the template's `charts = []`
+                array.set_trailing("");
+                array.set_trailing_comma(false);
+            }
+        }
+        ChartsItem::Tables(tables) => {
+            let index = tables
+                .iter()
+                .position(|chart| chart_name(Some(chart)) == Some(name))
+                .ok_or_else(|| chart_not_found(name))?;
+            tables.remove(index);
+        }
+    }
+    Ok(())
+}
+
+/// Charts may be written as an inline array, as in the template, or as `[[app.charts]]` tables.
+enum ChartsItem<'a> {
+    Array(&'a mut Array),
+    Tables(&'a mut toml_edit::ArrayOfTables),
+}
+
+fn charts_item(document: &mut DocumentMut) -> Result<ChartsItem<'_>, CliError> {
+    let item = document
+        .get_mut("app")
+        .and_then(|app| app.as_table_like_mut())
+        .and_then(|app| app.get_mut("charts"));
+    match item {
+        Some(toml_edit::Item::ArrayOfTables(tables)) => Ok(ChartsItem::Tables(tables)),
+        Some(item) => item
+            .as_array_mut()
+            .map(ChartsItem::Array)
+            .ok_or_else(|| CliError::usage("app.charts isn't an array")),
+        None => Err(CliError::usage("app.charts is missing")),
+    }
+}
+
+fn find_chart<'a>(charts: ChartsItem<'a>, name: &str) -> Result<&'a mut dyn TableLike, CliError> {
+    let found = match charts {
+        ChartsItem::Array(array) => array
+            .iter_mut()
+            .filter_map(|chart| chart.as_inline_table_mut())
+            .find(|chart| chart_name(Some(&**chart)) == Some(name))
+            .map(|chart| chart as &mut dyn TableLike),
+        ChartsItem::Tables(tables) => tables
+            .iter_mut()
+            .find(|chart| chart_name(Some(&**chart)) == Some(name))
+            .map(|chart| chart as &mut dyn TableLike),
+    };
+    found.ok_or_else(|| chart_not_found(name))
+}
+
+fn chart_name<T: TableLike + ?Sized>(chart: Option<&T>) -> Option<&str> {
+    chart?.get("name")?.as_str()
+}
+
+/// Pushes onto an inline array, one chart per line, following the layout of existing entries.
+fn push_inline(array: &mut Array, fields: InlineTable) {
+    let prefix = match array.iter().last() {
+        Some(last) => last
+            .decor()
+            .prefix()
+            .and_then(|prefix| prefix.as_str())
+            .unwrap_or(" ")
+            .to_string(),
+        None => {
+            array.set_trailing("\n");
+            array.set_trailing_comma(true);
+            "\n    ".to_string()
+        }
+    };
+    array.push_formatted(toml_edit::Value::InlineTable(fields).decorated(prefix, ""));
+}
+
+fn validate_chart_value(field: &str, value: &str) -> Result<(), CliError> {
+    validate_dns_label(value)
+        .map_err(|e| CliError::usage(format!("Invalid chart {}: {}", field, e)))
+}
+
+fn chart_not_found(name: &str) -> CliError {
+    CliError::usage(format!("No chart named {}", name))
+        .with_suggestion("run `deplio chart list` to see the configured charts")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib::app_config::{
+        APP_CONFIG_FILE_NAME, deserialize_app_config, write_app_config_template,
+    };
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn template_document() -> DocumentMut {
+        write_app_config_template("my-app", "https://deplio", "acme")
+            .parse()
+            .expect("template should parse")
+    }
+
+    fn chart(name: &str) -> ChartV1_0_0 {
+        ChartV1_0_0 {
+            name: name.to_string(),
+            url: format!("oci://charts/{}", name),
+            namespace: "default".to_string(),
+        }
+    }
+
+    fn charts_of(document: &DocumentMut) -> Vec<ChartV1_0_0> {
+        deserialize_app_config(&document.to_string())
+            .expect("document should be a valid app config")
+            .app
+            .charts
+    }
+
+    #[test]
+    fn test_add_chart_to_template_keeps_comments() {
+        let mut document = template_document();
+
+        add_chart(&mut document, &[], &chart("web")).unwrap();
+        let existing = charts_of(&document);
+        add_chart(&mut document, &existing, &chart("worker")).unwrap();
+
+        let contents = document.to_string();
+        assert!(contents.contains(
+            "charts = [\n    { name = \"web\", url = \"oci://charts/web\", namespace = \"default\" },\n    { name = \"worker\", url = \"oci://charts/worker\", namespace = \"default\" },\n]"
+        ));
+        assert!(contents.contains("# chart objects are defined in the format:"));
+        assert!(contents.contains("### The SDLC to use for this application."));
+        assert_eq!(charts_of(&document).len(), 2);
+    }
+
+    #[test]
+    fn test_add_chart_rejects_duplicate_name() {
+        let mut document = template_document();
+        add_chart(&mut document, &[], &chart("web")).unwrap();
+
+        let existing = charts_of(&document);
+        let error = add_chart(&mut document, &existing, &chart("web")).unwrap_err();
+        assert_eq!(error.message, "A chart named web already exists");
+    }
+
+    #[test]
+    fn test_add_chart_rejects_invalid_namespace() {
+        let mut document = template_document();
+        let mut invalid = chart("web");
+        invalid.namespace = "Not_Valid".to_string();
+
+        let error = add_chart(&mut document, &[], &invalid).unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid chart namespace: use only lowercase letters, digits and '-'"
+        );
+        assert!(charts_of(&document).is_empty());
+    }
+
+    #[test]
+    fn test_update_chart() {
+        let mut document = template_document();
+        add_chart(&mut document, &[], &chart("web")).unwrap();
+
+        update_chart(&mut document, "web", None, Some("frontend")).unwrap();
+
+        let charts = charts_of(&document);
+        assert_eq!(charts[0].namespace, "frontend");
+        assert_eq!(charts[0].url, "oci://charts/web");
+        assert!(update_chart(&mut document, "missing", Some("x"), None).is_err());
+        assert!(update_chart(&mut document, "web", None, None).is_err());
+    }
+
+    #[test]
+    fn test_remove_chart_restores_empty_array() {
+        let mut document = template_document();
+        add_chart(&mut document, &[], &chart("web")).unwrap();
+
+        remove_chart(&mut document, "web").unwrap();
+
+        assert_eq!(
+            document.to_string(),
+            write_app_config_template("my-app", "https://deplio", "acme")
+        );
+        assert_eq!(
+            remove_chart(&mut document, "web").unwrap_err().message,
+            "No chart named web"
+        );
+    }
+
+    #[test]
+    fn test_edit_array_of_tables() {
+        let mut document: DocumentMut = write_app_config_template("my-app", "https://deplio", "acme")
+            .replace("charts = []\n", "")
+            .replace(
+                "sdlc = \"default\"",
+                "sdlc = \"default\"\n\n[[app.charts]]\nname = \"web\"\nurl = \"oci://charts/web\"\nnamespace = \"default\"\n",
+            )
+            .parse()
+            .unwrap();
+
+        let existing = charts_of(&document);
+        add_chart(&mut document, &existing, &chart("worker")).unwrap();
+        update_chart(&mut document, "web", Some("oci://charts/web2"), None).unwrap();
+        remove_chart(&mut document, "worker").unwrap();
+
+        let charts = charts_of(&document);
+        assert_eq!(charts.len(), 1);
+        assert_eq!(charts[0].url, "oci://charts/web2");
+    }
+
+    #[test]
+    fn test_edit_charts_reports_the_affected_chart() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        fs::write(
+            temp_dir.path().join(APP_CONFIG_FILE_NAME),
+            template_document().to_string(),
+        )
+        .expect("Failed to write app config");
+        let edit = |command: ChartCommands| {
+            let file = AppConfigFile::load(temp_dir.path()).expect("app config should load");
+            edit_charts(&file, &command).expect("edit should succeed")
+        };
+
+        let added = edit(ChartCommands::Add {
+            name: "web".to_string(),
+            url: "oci://charts/web".to_string(),
+            namespace: "default".to_string(),
+        });
+        assert_eq!(added.action, ChartAction::Added);
+        assert_eq!(added.chart.url, "oci://charts/web");
+        assert!(added.render_text().is_empty());
+
+        let updated = edit(ChartCommands::Update {
+            name: "web".to_string(),
+            url: None,
+            namespace: Some("web".to_string()),
+        });
+        assert_eq!(updated.action, ChartAction::Updated);
+        assert_eq!(updated.chart.namespace, "web");
+        assert_eq!(updated.chart.url, "oci://charts/web");
+
+        let removed = edit(ChartCommands::Remove {
+            name: "web".to_string(),
+        });
+        let json: serde_json::Value =
+            serde_json::from_str(&output::render(OutputFormat::Json, &removed).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "action": "removed",
+                "chart": { "name": "web", "url": "oci://charts/web", "namespace": "web" },
+                "path": temp_dir.path().join(APP_CONFIG_FILE_NAME),
+            })
+        );
+    }
+
+    #[test]
+    fn test_list_charts() {
+        assert_eq!(list_charts(&[]), vec!["No charts configured"]);
+        assert_eq!(
+            list_charts(&[chart("web"), chart("background-worker")]),
+            vec![
+                "NAME               NAMESPACE  URL",
+                "web                default    oci://charts/web",
+                "background-worker  default    oci://charts/background-worker",
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use crate::config::{self, CONFIG_KEYS, PROFILE_KEYS};
+use crate::error::CliError;
+use crate::parser::{Cli, CompletionShell};
+use crate::project::AppConfigFile;
+use clap::CommandFactory;
+use clap_complete::CompletionCandidate;
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+use std::io::Write;
+use std::path::Path;
+
+/// The environment variable the registration scripts set when calling back into deplio.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+const BIN_NAME: &str = "deplio";
+
+/// Writes the script registering deplio's completions with `shell`.
+///
+/// The script calls back into `deplio` on every completion, so values read from the project
+/// and the configuration, such as chart names, are always current.
+pub fn write_registration(shell: CompletionShell, out: &mut dyn Write) -> Result<(), CliError> {
+    let completer: &dyn EnvCompleter = match shell {
+        CompletionShell::Bash => &Bash,
+        CompletionShell::Zsh => &Zsh,
+        CompletionShell::Fish => &Fish,
+        CompletionShell::Powershell => &Powershell,
+        CompletionShell::Elvish => &Elvish,
+    };
+    completer
+        .write_registration(COMPLETE_VAR, BIN_NAME, BIN_NAME, BIN_NAME, out)
+        .map_err(|e| CliError::io(format!("Unable to write completions: {}", e)))
+}
+
+/// Writes the man page of deplio to `out`, or a page per command into `out_dir`.
+pub fn write_man_pages(out_dir: Option<&Path>, out: &mut dyn Write) -> Result<(), CliError> {
+    let result = match out_dir {
+        Some(out_dir) => clap_mangen::generate_to(Cli::command(), out_dir),
+        None => clap_mangen::Man::new(Cli::command()).render(out),
+    };
+    result.map_err(|e| CliError::io(format!("Unable to write man pages: {}", e)))
+}
+
+/// Completes the names of the charts in the project in the working directory.
+pub fn chart_names() -> Vec<CompletionCandidate> {
+    let Ok(project_dir) = std::env::current_dir() else {
+        return Vec::new();
+    };
+    chart_names_in(&project_dir)
+}
+
+fn chart_names_in(project_dir: &Path) -> Vec<CompletionCandidate> {
+    // completions must stay quiet, so a missing or broken project completes nothing
+    let Ok(file) = AppConfigFile::load(project_dir) else {
+        return Vec::new();
+    };
+    file.config
+        .app
+        .charts
+        .into_iter()
+        .map(|chart| CompletionCandidate::new(chart.name).help(Some(chart.namespace.into())))
+        .collect()
+}
+
+/// Completes the names of the configured profiles.
+pub fn profile_names() -> Vec<CompletionCandidate> {
+    let Ok(config) = config::load_config(None) else {
+        return Vec::new();
+    };
+    config
+        .profiles
+        .into_keys()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Completes the configuration keys, including those of every configured profile.
+pub fn config_keys() -> Vec<CompletionCandidate> {
+    let profiles = config::load_config(None)
+        .map(|config| config.profiles.into_keys().collect())
+        .unwrap_or_else(|_| Vec::new());
+    known_config_keys(&profiles)
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+fn known_config_keys(profiles: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = CONFIG_KEYS.iter().map(|key| key.to_string()).collect();
+    for profile in profiles {
+        keys.extend(
+            PROFILE_KEYS
+                .iter()
+                .map(|key| format!("profiles.{}.{}", profile, key)),
+        );
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib::app_config::{APP_CONFIG_FILE_NAME, write_app_config_template};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn values(candidates: Vec<CompletionCandidate>) -> Vec<String> {
+        candidates
+            .iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_write_registration_for_every_shell() {
+        for shell in [
+            CompletionShell::Bash,
+            CompletionShell::Zsh,
+            CompletionShell::Fish,
+            CompletionShell::Powershell,
+            CompletionShell::Elvish,
+        ] {
+            let mut out = Vec::new();
+            write_registration(shell, &mut out).expect("registration should be written");
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains(COMPLETE_VAR));
+            assert!(script.contains(BIN_NAME));
+        }
+    }
+
+    #[test]
+    fn test_write_man_page() {
+        let mut out = Vec::new();
+        write_man_pages(None, &mut out).expect("man page should be written");
+        let page = String::from_utf8(out).unwrap();
+        assert!(page.starts_with(".ie"));
+        assert!(page.contains("deplio"));
+    }
+
+    #[test]
+    fn test_write_man_pages_to_dir() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let mut out = Vec::new();
+        write_man_pages(Some(temp_dir.path()), &mut out).expect("man pages should be written");
+
+        assert!(out.is_empty());
+        assert!(temp_dir.path().join("deplio.1").exists());
+        assert!(temp_dir.path().join("deplio-chart-add.1").exists());
+    }
+
+    #[test]
+    fn test_chart_names() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        assert!(chart_names_in(temp_dir.path()).is_empty());
+
+        let contents = write_app_config_template("my-app", "https://deplio", "acme").replace(
+            "charts = []",
+            "charts = [{ name = \"web\", url = \"oci://charts/web\", namespace = \"default\" }]",
+        );
+        fs::write(temp_dir.path().join(APP_CONFIG_FILE_NAME), contents)
+            .expect("Failed to write app config");
+        assert_eq!(values(chart_names_in(temp_dir.path())), vec!["web"]);
+    }
+
+    #[test]
+    fn test_known_config_keys() {
+        let keys = known_config_keys(&["internal".to_string()]);
+        assert!(keys.contains(&"defaults.owner".to_string()));
+        assert!(keys.contains(&"profiles.internal.token".to_string()));
+    }
+}
@@ -1,115 +1,1010 @@
+use crate::config_versioning::{insert_root_value, upgrade_config};
+use crate::error::{CliError, ErrorCategory, TomlSourceError};
+use crate::output::{self, OutputFormat, Render};
+use crate::parser::ConfigCommands;
 use crate::templates::CONFIG_TEMPLATE;
 use dirs;
-use serde::Deserialize;
-use std::{env, fs, path::PathBuf, process};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process,
+};
+use thiserror::Error;
+use toml::{Table, Value};
+use toml_edit::DocumentMut;
+use tracing::{debug, debug_span, trace, warn};
 
 const DEPLIO_CONFIG_FILE_NAME: &str = ".deplio";
+const SYSTEM_CONFIG_PATH: &str = "/etc/deplio";
 
-#[derive(Debug)]
+/// Keys that can be edited with `deplio config set` and `deplio config unset`.
+/// Profile values are edited with `profiles.<name>.<key>`, see [`PROFILE_KEYS`].
+pub const CONFIG_KEYS: &[&str] = &[
+    "default_profile",
+    "defaults.deplio_server",
+    "defaults.owner",
+    "debug.synth_working_dir",
+    "debug.override_params",
+];
+
+/// Keys that can be set inside a `[profiles.<name>]` section.
+pub const PROFILE_KEYS: &[&str] = &["deplio_server", "owner", "token"];
+
+/// Environment variables that override configuration values, mapped to their dotted key.
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("DEPLIO_PROFILE", "default_profile"),
+    ("DEPLIO_SERVER", "defaults.deplio_server"),
+    ("DEPLIO_OWNER", "defaults.owner"),
+    ("DEPLIO_SYNTH_WORKING_DIR", "debug.synth_working_dir"),
+    ("DEPLIO_OVERRIDE_PARAMS", "debug.override_params"),
+];
+
+#[derive(Debug, Error)]
 pub enum ConfigurationError {
+    #[error("Unable to find home directory")]
     HomeDirNotFound,
+    #[error("Unable to access configuration: {0}")]
     IoFail(String),
+    #[error("Unable to read configuration: {0}")]
     FileReadFail(String),
+    #[error("Invalid configuration: {0}")]
     DeserializationFail(String),
+    /// A file isn't valid, along with the layer it belongs to.
+    #[error("{0}")]
+    InvalidToml(TomlSourceError, ConfigLayer),
+    #[error("Unable to upgrade configuration: {0}")]
+    UpgradeFail(String),
+}
+
+impl From<ConfigurationError> for CliError {
+    fn from(error: ConfigurationError) -> Self {
+        match error {
+            ConfigurationError::HomeDirNotFound => {
+                CliError::new(ErrorCategory::Environment, error.to_string())
+                    .with_suggestion("set the HOME environment variable")
+            }
+            ConfigurationError::IoFail(_) | ConfigurationError::FileReadFail(_) => {
+                CliError::io(error.to_string())
+            }
+            ConfigurationError::DeserializationFail(_) => {
+                CliError::new(ErrorCategory::Config, error.to_string())
+                    .with_suggestion("run `deplio config list --show-origin` to find the value")
+            }
+            ConfigurationError::InvalidToml(source, layer) => {
+                // --overwrite only recreates ~/.deplio, it would leave any other file broken
+                let suggestion = match layer {
+                    ConfigLayer::User => {
+                        "fix the file, or recreate it from the template with `deplio config --overwrite`"
+                            .to_string()
+                    }
+                    _ => format!("fix {}", source.path.display()),
+                };
+                CliError::toml(ErrorCategory::Config, source).with_suggestion(suggestion)
+            }
+            ConfigurationError::UpgradeFail(_) => {
+                CliError::new(ErrorCategory::Config, error.to_string()).with_suggestion(
+                    "the file may have been written by a newer deplio, try updating deplio",
+                )
+            }
+        }
+    }
 }
 
 pub fn handle_command(
     edit: &bool,
     overwrite: &bool,
     home_dir_override: Option<&str>,
-) -> Result<(), &'static str> {
-    let home_dir = match home_dir_override {
-        Some(path) => PathBuf::from(path),
-        None => dirs::home_dir().ok_or("Unable to find home directory")?,
-    };
-    let config_path = home_dir.join(DEPLIO_CONFIG_FILE_NAME);
-    match fs::exists(&config_path) {
+) -> Result<ConfigFileResult, CliError> {
+    let config_path = user_config_path(home_dir_override)?;
+    let created = match fs::exists(&config_path) {
         Ok(true) => {
             if *overwrite {
-                fs::remove_file(&config_path).expect("Unable to delete old configuration file");
-                fs::write(&config_path, CONFIG_TEMPLATE)
-                    .expect("Unable to create configuration file");
-            } else {
-                println!("Configuration already exists at: {:?}", config_path);
+                fs::remove_file(&config_path).map_err(|e| {
+                    CliError::io(format!("Unable to delete old configuration file: {}", e))
+                })?;
+                write_config(&config_path, CONFIG_TEMPLATE)?;
             }
+            *overwrite
         }
         Ok(false) => {
-            fs::write(&config_path, CONFIG_TEMPLATE).expect("Unable to create configuration file");
+            write_config(&config_path, CONFIG_TEMPLATE)?;
+            true
         }
-        Err(e) => {
-            panic!("Error accessing deplio configuration: {}", e);
-        }
-    }
+        Err(e) => return Err(ConfigurationError::IoFail(e.to_string()).into()),
+    };
 
     if *edit {
         match env::var("EDITOR") {
             Ok(editor) => {
-                println!("Opening configuration file in editor: {}", editor);
-                process::Command::new(editor)
+                // stdout is kept for the command's result
+                eprintln!("Opening configuration file in editor: {}", editor);
+                process::Command::new(&editor)
                     .arg(&config_path)
                     .status()
-                    .expect("Failed to open editor");
+                    .map_err(|e| {
+                        CliError::new(
+                            ErrorCategory::Environment,
+                            format!("Failed to open editor {}: {}", editor, e),
+                        )
+                        .with_suggestion("check that EDITOR points at an installed program")
+                    })?;
             }
             Err(_) => {
-                return Err("EDITOR environment variable not set. Unable to open editor.");
+                return Err(CliError::new(
+                    ErrorCategory::Environment,
+                    "EDITOR environment variable not set. Unable to open editor.",
+                )
+                .with_suggestion("set EDITOR, e.g. `export EDITOR=vim`"));
             }
         }
     }
 
+    Ok(ConfigFileResult {
+        path: config_path,
+        created,
+    })
+}
+
+/// The outcome of `deplio config` without a subcommand.
+#[derive(Debug, Serialize)]
+pub struct ConfigFileResult {
+    pub path: PathBuf,
+    /// False when the file already existed and was left as is.
+    pub created: bool,
+}
+
+impl Render for ConfigFileResult {
+    fn render_text(&self) -> Vec<String> {
+        if self.created {
+            Vec::new()
+        } else {
+            vec![format!("Configuration already exists at: {:?}", self.path)]
+        }
+    }
+}
+
+/// Runs a `deplio config` subcommand. `profile` is the `--profile` flag, `get` and `list`
+/// apply it like every other command does.
+pub fn handle_subcommand(
+    command: &ConfigCommands,
+    home_dir_override: Option<&str>,
+    profile: Option<&str>,
+    output: OutputFormat,
+) -> Result<(), CliError> {
+    let config_path = user_config_path(home_dir_override)?;
+
+    match command {
+        ConfigCommands::Get { key, show_secret } => {
+            let resolved = resolve_user_config(home_dir_override, profile)?;
+            match resolved.values.get(key) {
+                Some(resolved_value) => {
+                    output::print(output, &ConfigEntry::new(key, resolved_value, *show_secret))?
+                }
+                None => {
+                    return Err(
+                        CliError::usage(format!("{} is not set", key)).with_suggestion(
+                            "run `deplio config list` to see the values that are set",
+                        ),
+                    );
+                }
+            }
+        }
+        ConfigCommands::Set { key, value } => {
+            set_value(&config_path, key, value)?;
+            output::print(output, &SetResult::new(key, value, config_path))?;
+        }
+        ConfigCommands::Unset { key } => {
+            let removed = unset_value(&config_path, key)?;
+            output::print(
+                output,
+                &UnsetResult {
+                    key: key.clone(),
+                    path: config_path,
+                    removed,
+                },
+            )?;
+        }
+        ConfigCommands::List { show_origin } => {
+            let resolved = resolve_user_config(home_dir_override, profile)?;
+            output::print(output, &list_values(&resolved, *show_origin))?;
+        }
+    }
+
     Ok(())
 }
 
-pub fn load_config(home_dir_override: Option<&str>) -> Result<Configuration, ConfigurationError> {
+fn resolve_user_config(
+    home_dir_override: Option<&str>,
+    profile: Option<&str>,
+) -> Result<ResolvedConfiguration, CliError> {
+    let sources = ConfigSources::discover(home_dir_override)?;
+    let mut resolved = resolve_config(&sources)?;
+    resolved.select_profile(profile)?;
+    Ok(resolved)
+}
+
+fn user_config_path(home_dir_override: Option<&str>) -> Result<PathBuf, ConfigurationError> {
     let home_dir = match home_dir_override {
         Some(path) => PathBuf::from(path),
         None => dirs::home_dir().ok_or(ConfigurationError::HomeDirNotFound)?,
     };
+    Ok(home_dir.join(DEPLIO_CONFIG_FILE_NAME))
+}
 
-    //dirs::home_dir().ok_or(ConfigurationError::HomeDirNotFound)?;
-    let config_path = home_dir.join(DEPLIO_CONFIG_FILE_NAME);
-    let config_exists = fs::exists(&config_path);
-    match config_exists {
-        Ok(true) => (),
-        Ok(false) => {
-            return Ok(Configuration {
-                debug: Debug {
-                    synth_working_dir: None,
-                    override_params: None,
-                },
-                defaults: Defaults {
-                    deplio_server: None,
-                    owner: None,
-                },
-            });
+fn read_config(config_path: &Path) -> Result<String, CliError> {
+    fs::read_to_string(config_path)
+        .map_err(|e| CliError::io(format!("Unable to read {}: {}", config_path.display(), e)))
+}
+
+fn write_config(config_path: &Path, contents: &str) -> Result<(), CliError> {
+    debug!(path = %config_path.display(), "writing configuration file");
+    fs::write(config_path, contents)
+        .map_err(|e| CliError::io(format!("Unable to write {}: {}", config_path.display(), e)))
+}
+
+/// Sets `key` in the configuration file at `config_path`, creating the file from the
+/// template if needed. Comments and layout of the existing file are kept.
+pub fn set_value(config_path: &Path, key: &str, value: &str) -> Result<(), CliError> {
+    let (sections, field) = split_known_key(key)?;
+    let contents = match fs::exists(config_path) {
+        Ok(true) => read_config(config_path)?,
+        Ok(false) => CONFIG_TEMPLATE.to_string(),
+        Err(e) => return Err(ConfigurationError::IoFail(e.to_string()).into()),
+    };
+    let mut document = parse_document(config_path, &contents)?;
+
+    if sections.is_empty() && !document.contains_key(field) {
+        insert_root_value(&mut document, field, value);
+        return write_config(config_path, &document.to_string());
+    }
+
+    // new tables are written before the trailing comments, which belong to the last table
+    let trailing = document.trailing().as_str().unwrap_or_default().to_string();
+    let mut moved_trailing = false;
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for (depth, section) in sections.iter().enumerate() {
+        if !table.contains_key(section) {
+            let mut new_table = toml_edit::Table::new();
+            if depth + 1 < sections.len() {
+                // parent tables like `profiles` only exist to hold named sections
+                new_table.set_implicit(true);
+            } else {
+                let trailing = trailing.trim_end();
+                new_table.decor_mut().set_prefix(if trailing.is_empty() {
+                    "\n".to_string()
+                } else {
+                    format!("{}\n\n", trailing)
+                });
+                moved_trailing = true;
+            }
+            table.insert(section, toml_edit::Item::Table(new_table));
         }
-        Err(e) => return Err(ConfigurationError::IoFail(e.to_string())),
+        table = match table
+            .get_mut(section)
+            .and_then(|item| item.as_table_like_mut())
+        {
+            Some(table) => table,
+            None => {
+                return Err(CliError::new(
+                    ErrorCategory::Config,
+                    format!(
+                        "{} is not a table in {}",
+                        sections[..=depth].join("."),
+                        config_path.display()
+                    ),
+                ));
+            }
+        };
+    }
+    table.insert(field, toml_edit::value(value));
+    if moved_trailing {
+        document.set_trailing("");
     }
-    let contents = fs::read_to_string(config_path);
-    if let Err(err) = &contents {
-        return Err(ConfigurationError::FileReadFail(err.to_string()));
+
+    write_config(config_path, &document.to_string())
+}
+
+/// Removes `key` from the configuration file at `config_path`.
+/// Returns false if the key was not set in the file.
+pub fn unset_value(config_path: &Path, key: &str) -> Result<bool, CliError> {
+    let (sections, field) = split_known_key(key)?;
+    if !fs::exists(config_path).unwrap_or(false) {
+        return Ok(false);
     }
+    let contents = read_config(config_path)?;
+    let mut document = parse_document(config_path, &contents)?;
 
-    match toml::from_str::<Configuration>(&contents.unwrap()) {
-        Ok(config) => Ok(config),
-        Err(err) => Err(ConfigurationError::DeserializationFail(
-            err.message().to_string(),
-        )),
+    let mut table: Option<&mut dyn toml_edit::TableLike> = Some(document.as_table_mut());
+    for section in &sections {
+        table = table
+            .and_then(|table| table.get_mut(section))
+            .and_then(|item| item.as_table_like_mut());
     }
+    let removed = table.and_then(|table| table.remove(field)).is_some();
+    if removed {
+        write_config(config_path, &document.to_string())?;
+    }
+    Ok(removed)
+}
+
+/// Formats every effective value as `key = value`, optionally prefixed with its origin.
+/// Secrets are always masked.
+pub fn list_values(resolved: &ResolvedConfiguration, show_origin: bool) -> ConfigList {
+    ConfigList {
+        values: resolved
+            .values
+            .iter()
+            .map(|(key, resolved_value)| ConfigEntry::new(key, resolved_value, false))
+            .collect(),
+        show_origin,
+    }
+}
+
+/// An effective configuration value, as printed by `deplio config get`.
+#[derive(Debug, Serialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    /// [`MASKED_VALUE`] for secrets, unless they were asked for.
+    pub value: Value,
+    pub masked: bool,
+    pub origin: ConfigOrigin,
+}
+
+impl ConfigEntry {
+    fn new(key: &str, resolved_value: &ResolvedValue, show_secret: bool) -> Self {
+        let masked = is_secret_key(key) && !show_secret;
+        ConfigEntry {
+            key: key.to_string(),
+            value: if masked {
+                Value::String(MASKED_VALUE.to_string())
+            } else {
+                resolved_value.value.clone()
+            },
+            masked,
+            origin: resolved_value.origin.clone(),
+        }
+    }
+}
+
+/// Printed in place of secret values.
+pub const MASKED_VALUE: &str = "********";
+
+/// Whether a dotted key holds a credential, e.g. `profiles.prod.token`.
+pub fn is_secret_key(key: &str) -> bool {
+    matches!(
+        key.split('.').collect::<Vec<_>>().as_slice(),
+        ["profiles", _, "token"]
+    )
+}
+
+impl Render for ConfigEntry {
+    fn render_text(&self) -> Vec<String> {
+        vec![format_value(&self.value)]
+    }
+}
+
+/// Every effective configuration value, as printed by `deplio config list`.
+#[derive(Debug, Serialize)]
+pub struct ConfigList {
+    pub values: Vec<ConfigEntry>,
+    /// Only affects the text output, origins are always part of the other formats.
+    #[serde(skip)]
+    pub show_origin: bool,
+}
+
+impl Render for ConfigList {
+    fn render_text(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .map(|entry| {
+                if self.show_origin {
+                    format!("{}\t{} = {}", entry.origin, entry.key, entry.value)
+                } else {
+                    format!("{} = {}", entry.key, entry.value)
+                }
+            })
+            .collect()
+    }
+}
+
+/// The outcome of `deplio config set`. Prints nothing as text.
+#[derive(Debug, Serialize)]
+pub struct SetResult {
+    pub key: String,
+    /// [`MASKED_VALUE`] for secrets.
+    pub value: String,
+    pub masked: bool,
+    pub path: PathBuf,
+}
+
+impl SetResult {
+    fn new(key: &str, value: &str, path: PathBuf) -> Self {
+        let masked = is_secret_key(key);
+        SetResult {
+            key: key.to_string(),
+            value: if masked {
+                MASKED_VALUE.to_string()
+            } else {
+                value.to_string()
+            },
+            masked,
+            path,
+        }
+    }
+}
+
+impl Render for SetResult {
+    fn render_text(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// The outcome of `deplio config unset`.
+#[derive(Debug, Serialize)]
+pub struct UnsetResult {
+    pub key: String,
+    pub path: PathBuf,
+    /// False when the key wasn't set in the file.
+    pub removed: bool,
+}
+
+impl Render for UnsetResult {
+    fn render_text(&self) -> Vec<String> {
+        if self.removed {
+            Vec::new()
+        } else {
+            vec![format!(
+                "{} is not set in {}",
+                self.key,
+                self.path.display()
+            )]
+        }
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Splits a known dotted key into its parent tables and the field name.
+fn split_known_key(key: &str) -> Result<(Vec<&str>, &str), CliError> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let is_profile_key = matches!(
+        parts.as_slice(),
+        ["profiles", name, field] if !name.is_empty() && PROFILE_KEYS.contains(field)
+    );
+    if !CONFIG_KEYS.contains(&key) && !is_profile_key {
+        return Err(
+            CliError::usage(format!("Unknown configuration key {}", key)).with_suggestion(format!(
+                "valid keys are: {}, profiles.<name>.{{{}}}",
+                CONFIG_KEYS.join(", "),
+                PROFILE_KEYS.join(",")
+            )),
+        );
+    }
+    let field = parts.pop().expect("split always yields a field");
+    Ok((parts, field))
 }
 
-#[derive(Deserialize, Debug)]
+/// Parses a configuration file for editing, upgrading it to the current version first so
+/// edits always land in the current format.
+fn parse_document(config_path: &Path, contents: &str) -> Result<DocumentMut, CliError> {
+    contents.parse::<DocumentMut>().map_err(|e| {
+        ConfigurationError::InvalidToml(
+            TomlSourceError::new(config_path, contents, e.span(), e.message()),
+            ConfigLayer::User,
+        )
+    })?;
+    let upgraded = upgrade_config(contents).map_err(|e| {
+        ConfigurationError::UpgradeFail(format!("{}: {}", config_path.display(), e))
+    })?;
+    Ok(upgraded
+        .parse::<DocumentMut>()
+        .expect("upgraders keep valid TOML valid"))
+}
+
+pub fn load_config(home_dir_override: Option<&str>) -> Result<Configuration, ConfigurationError> {
+    let sources = ConfigSources::discover(home_dir_override)?;
+    Ok(resolve_config(&sources)?.config)
+}
+
+/// Loads the configuration from the layers of `environment` rather than of the process.
+pub fn load_config_in(
+    environment: &ConfigEnvironment,
+) -> Result<Configuration, ConfigurationError> {
+    Ok(resolve_config(&ConfigSources::in_environment(environment))?.config)
+}
+
+/// Resolves the effective configuration by merging every layer in `sources`, in order.
+/// Values from later layers override values from earlier ones.
+pub fn resolve_config(
+    sources: &ConfigSources,
+) -> Result<ResolvedConfiguration, ConfigurationError> {
+    let _span = debug_span!("resolve_config").entered();
+    let mut merged = Table::new();
+    let mut values = BTreeMap::new();
+
+    for (layer, path) in &sources.files {
+        if !fs::exists(path).map_err(|e| ConfigurationError::IoFail(e.to_string()))? {
+            trace!(%layer, path = %path.display(), "no configuration file");
+            continue;
+        }
+        debug!(%layer, path = %path.display(), "reading configuration layer");
+        let contents = fs::read_to_string(path)
+            .map_err(|e| ConfigurationError::FileReadFail(e.to_string()))?;
+        toml::from_str::<Table>(&contents).map_err(|e| toml_error(*layer, path, &contents, &e))?;
+        let upgraded = upgrade_config(&contents)
+            .map_err(|e| ConfigurationError::UpgradeFail(format!("{}: {}", path.display(), e)))?;
+        let mut table = toml::from_str::<Table>(&upgraded)
+            .map_err(|e| toml_error(*layer, path, &upgraded, &e))?;
+        check_layer(*layer, path, &contents, &upgraded)?;
+        // the version describes the file itself, it isn't a setting to merge
+        table.remove("version");
+        drop_restricted_keys(*layer, path, &mut table);
+        let origin = ConfigOrigin {
+            layer: *layer,
+            source: path.display().to_string(),
+            profile: None,
+        };
+        merge_table(&mut merged, table, "", &origin, &mut values);
+    }
+
+    for (name, value) in &sources.env {
+        let Some((_, key)) = ENV_OVERRIDES.iter().find(|(var, _)| var == name) else {
+            continue;
+        };
+        debug!(var = name.as_str(), key, "applying environment override");
+        let origin = ConfigOrigin {
+            layer: ConfigLayer::Environment,
+            source: name.clone(),
+            profile: None,
+        };
+        let mut parts = key.rsplit('.');
+        let field = parts.next().expect("split always yields a field");
+        let mut table = Table::from_iter([(field.to_string(), Value::String(value.clone()))]);
+        for section in parts {
+            table = Table::from_iter([(section.to_string(), Value::Table(table))]);
+        }
+        merge_table(&mut merged, table, "", &origin, &mut values);
+    }
+
+    let mut config = Value::Table(merged)
+        .try_into::<Configuration>()
+        .map_err(|err| ConfigurationError::DeserializationFail(err.message().to_string()))?;
+    config.origins = values
+        .iter()
+        .map(|(key, resolved_value)| (key.clone(), resolved_value.origin.clone()))
+        .collect();
+
+    debug!(values = values.len(), "resolved configuration");
+    Ok(ResolvedConfiguration { config, values })
+}
+
+/// Top-level keys each file layer may not set. `debug` replaces the command line and working
+/// dir, so only the user's own file may set it. A project `.deplio` comes with the repository,
+/// so it also mustn't choose or define profiles, which hold tokens.
+fn restricted_keys(layer: ConfigLayer) -> &'static [&'static str] {
+    match layer {
+        ConfigLayer::System | ConfigLayer::Xdg => &["debug"],
+        ConfigLayer::Project => &["debug", "profiles", "default_profile"],
+        ConfigLayer::User | ConfigLayer::Environment => &[],
+    }
+}
+
+/// Removes the keys `layer` may not set, see [`restricted_keys`], warning about each one.
+fn drop_restricted_keys(layer: ConfigLayer, path: &Path, table: &mut Table) {
+    for key in restricted_keys(layer) {
+        if table.remove(*key).is_some() {
+            warn!(
+                %layer,
+                path = %path.display(),
+                "ignoring {} in this configuration file, only ~/.deplio may set it",
+                key
+            );
+        }
+    }
+}
+
+/// Checks a single layer against [`Configuration`] so type errors point at the file they're in.
+/// Errors are located in the file as written when possible, rather than in its upgraded form.
+fn check_layer(
+    layer: ConfigLayer,
+    path: &Path,
+    original: &str,
+    upgraded: &str,
+) -> Result<(), ConfigurationError> {
+    let Err(error) = toml::from_str::<Configuration>(upgraded) else {
+        return Ok(());
+    };
+    match toml::from_str::<Configuration>(original) {
+        Err(original_error) if original_error.message() == error.message() => {
+            Err(toml_error(layer, path, original, &original_error))
+        }
+        _ => Err(toml_error(layer, path, upgraded, &error)),
+    }
+}
+
+fn toml_error(
+    layer: ConfigLayer,
+    path: &Path,
+    contents: &str,
+    error: &toml::de::Error,
+) -> ConfigurationError {
+    ConfigurationError::InvalidToml(
+        TomlSourceError::new(path, contents, error.span(), error.message()),
+        layer,
+    )
+}
+
+fn merge_table(
+    target: &mut Table,
+    layer: Table,
+    prefix: &str,
+    origin: &ConfigOrigin,
+    values: &mut BTreeMap<String, ResolvedValue>,
+) {
+    for (key, value) in layer {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::Table(table) => {
+                let entry = target
+                    .entry(key)
+                    .or_insert_with(|| Value::Table(Table::new()));
+                if !entry.is_table() {
+                    *entry = Value::Table(Table::new());
+                }
+                if let Value::Table(entry_table) = entry {
+                    merge_table(entry_table, table, &path, origin, values);
+                }
+            }
+            value => {
+                values.insert(
+                    path,
+                    ResolvedValue {
+                        value: value.clone(),
+                        origin: origin.clone(),
+                    },
+                );
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The layers configuration can be read from, lowest precedence first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayer {
+    System,
+    Xdg,
+    User,
+    Project,
+    #[serde(rename = "env")]
+    Environment,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConfigLayer::System => "system",
+            ConfigLayer::Xdg => "xdg",
+            ConfigLayer::User => "user",
+            ConfigLayer::Project => "project",
+            ConfigLayer::Environment => "env",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Where an effective configuration value came from: the layer and the file or variable name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigOrigin {
+    pub layer: ConfigLayer,
+    pub source: String,
+    /// The profile a `[defaults]` value was taken from, when a profile is active.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.layer, self.source)?;
+        if let Some(profile) = &self.profile {
+            write!(f, " via profile {}", profile)?;
+        }
+        Ok(())
+    }
+}
+
+/// The set of configuration files and environment variables to resolve, in precedence order.
+#[derive(Debug, Default)]
+pub struct ConfigSources {
+    pub files: Vec<(ConfigLayer, PathBuf)>,
+    pub env: Vec<(String, String)>,
+}
+
+impl ConfigSources {
+    /// Builds the standard layer list: `/etc/deplio`, `$XDG_CONFIG_HOME/deplio/config.toml`,
+    /// `~/.deplio`, `./.deplio` and the `DEPLIO_*` environment variables.
+    pub fn discover(home_dir_override: Option<&str>) -> Result<Self, ConfigurationError> {
+        Ok(Self::in_environment(&ConfigEnvironment::from_process(
+            home_dir_override,
+        )?))
+    }
+
+    /// Builds the standard layer list for the given locations and variables.
+    pub fn in_environment(environment: &ConfigEnvironment) -> Self {
+        let xdg_dir = environment
+            .xdg_config_home
+            .clone()
+            .unwrap_or_else(|| environment.home_dir.join(".config"));
+
+        let mut files: Vec<(ConfigLayer, PathBuf)> = Vec::new();
+        for (layer, path) in [
+            (ConfigLayer::System, environment.system_path.clone()),
+            (ConfigLayer::Xdg, xdg_dir.join("deplio").join("config.toml")),
+            (
+                ConfigLayer::User,
+                environment.home_dir.join(DEPLIO_CONFIG_FILE_NAME),
+            ),
+            (
+                ConfigLayer::Project,
+                environment.current_dir.join(DEPLIO_CONFIG_FILE_NAME),
+            ),
+        ] {
+            // the project dir may be the home dir; don't let it claim the user layer's values
+            if !files.iter().any(|(_, existing)| *existing == path) {
+                files.push((layer, path));
+            }
+        }
+
+        let env = environment
+            .vars
+            .iter()
+            .filter(|(name, _)| name.starts_with("DEPLIO_"))
+            .cloned()
+            .collect();
+
+        ConfigSources { files, env }
+    }
+}
+
+/// The machine state configuration layers are discovered from.
+#[derive(Debug, Clone)]
+pub struct ConfigEnvironment {
+    pub home_dir: PathBuf,
+    pub system_path: PathBuf,
+    /// `$XDG_CONFIG_HOME`, `~/.config` is used when it's `None`.
+    pub xdg_config_home: Option<PathBuf>,
+    pub current_dir: PathBuf,
+    pub vars: Vec<(String, String)>,
+}
+
+impl ConfigEnvironment {
+    /// Reads the locations and variables of the running process. A home dir override also
+    /// replaces `$XDG_CONFIG_HOME`, so the XDG layer is looked up in the overridden home.
+    pub fn from_process(home_dir_override: Option<&str>) -> Result<Self, ConfigurationError> {
+        let (home_dir, xdg_config_home) = match home_dir_override {
+            Some(path) => (PathBuf::from(path), None),
+            None => (
+                dirs::home_dir().ok_or(ConfigurationError::HomeDirNotFound)?,
+                env::var_os("XDG_CONFIG_HOME")
+                    .filter(|dir| !dir.is_empty())
+                    .map(PathBuf::from),
+            ),
+        };
+        Ok(ConfigEnvironment {
+            home_dir,
+            system_path: PathBuf::from(SYSTEM_CONFIG_PATH),
+            xdg_config_home,
+            current_dir: env::current_dir()
+                .map_err(|e| ConfigurationError::IoFail(e.to_string()))?,
+            vars: env::vars().collect(),
+        })
+    }
+}
+
+/// A single effective configuration value and the layer that set it.
+#[derive(Debug, Clone)]
+pub struct ResolvedValue {
+    pub value: Value,
+    pub origin: ConfigOrigin,
+}
+
+/// The effective configuration along with the origin of every value that was set.
+#[derive(Debug, Default)]
+pub struct ResolvedConfiguration {
+    pub config: Configuration,
+    /// Effective values keyed by dotted path, e.g. `defaults.owner`.
+    pub values: BTreeMap<String, ResolvedValue>,
+}
+
+impl ResolvedConfiguration {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key).map(|resolved| &resolved.value)
+    }
+
+    pub fn origin_of(&self, key: &str) -> Option<&ConfigOrigin> {
+        self.values.get(key).map(|resolved| &resolved.origin)
+    }
+
+    /// Applies a profile like [`Configuration::select_profile`], so the effective values are
+    /// the ones commands use.
+    pub fn select_profile(&mut self, name: Option<&str>) -> Result<(), CliError> {
+        self.config.select_profile(name)?;
+        for field in PROFILE_DEFAULTS {
+            let key = format!("defaults.{}", field);
+            if let (Some(value), Some(origin)) = (
+                self.config.defaults.get(field),
+                self.config.origins.get(&key),
+            ) {
+                self.values.insert(
+                    key,
+                    ResolvedValue {
+                        value: Value::String(value.to_string()),
+                        origin: origin.clone(),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
 pub struct Configuration {
+    pub default_profile: Option<String>,
+    #[serde(default)]
     pub defaults: Defaults,
+    #[serde(default)]
     pub debug: Debug,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// The profile applied by [`Configuration::select_profile`], if any.
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    /// Where each value that was set came from, keyed by dotted path.
+    #[serde(skip)]
+    pub origins: BTreeMap<String, ConfigOrigin>,
+}
+
+impl Configuration {
+    /// Describes where an effective `[defaults]` field was set, e.g.
+    /// `defaults.owner from user (/home/me/.deplio)`. Names the profile's key when the active
+    /// profile provided the value.
+    pub fn defaults_source(&self, field: &str) -> String {
+        let key = format!("defaults.{}", field);
+        match self.origins.get(&key) {
+            Some(ConfigOrigin {
+                layer,
+                source,
+                profile: Some(profile),
+            }) => format!("profiles.{}.{} from {} ({})", profile, field, layer, source),
+            Some(origin) => format!("{} from {}", key, origin),
+            None => key,
+        }
+    }
+
+    /// Applies the named profile, or `default_profile` when no name is given, on top of
+    /// `[defaults]`. `DEPLIO_*` variables still win over the profile, as they do over every
+    /// file. Does nothing when neither name is set.
+    pub fn select_profile(&mut self, name: Option<&str>) -> Result<(), CliError> {
+        let Some(name) = name.or(self.default_profile.as_deref()) else {
+            return Ok(());
+        };
+        let Some(profile) = self.profiles.get(name) else {
+            let available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            let error = CliError::new(ErrorCategory::Config, format!("Profile {} not found", name));
+            return Err(if available.is_empty() {
+                error.with_suggestion(format!(
+                    "no profiles are configured, add one with `deplio config set profiles.{}.deplio_server <url>`",
+                    name
+                ))
+            } else {
+                error.with_suggestion(format!("available profiles: {}", available.join(", ")))
+            });
+        };
+
+        debug!(profile = name, "applying profile");
+        for field in PROFILE_DEFAULTS {
+            let Some(value) = profile.get(field) else {
+                continue;
+            };
+            let key = format!("defaults.{}", field);
+            if self
+                .origins
+                .get(&key)
+                .is_some_and(|origin| origin.layer == ConfigLayer::Environment)
+            {
+                debug!(key, "keeping environment override over profile");
+                continue;
+            }
+            self.defaults.set(field, value.to_string());
+            let profile_key = format!("profiles.{}.{}", name, field);
+            if let Some(origin) = self.origins.get(&profile_key).cloned() {
+                self.origins.insert(
+                    key,
+                    ConfigOrigin {
+                        profile: Some(name.to_string()),
+                        ..origin
+                    },
+                );
+            }
+        }
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// The credentials of the active profile. They're only used with the profile's own
+    /// `deplio_server`, so a server set elsewhere, e.g. by a project `.deplio`, never
+    /// receives them.
+    pub fn token(&self) -> Option<&str> {
+        let profile = self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))?;
+        match (&profile.deplio_server, &self.defaults.deplio_server) {
+            (Some(own_server), Some(server)) if own_server == server => profile.token.as_deref(),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct Defaults {
     pub deplio_server: Option<String>,
     pub owner: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+/// The `[defaults]` fields a profile can override.
+const PROFILE_DEFAULTS: &[&str] = &["deplio_server", "owner"];
+
+impl Defaults {
+    fn get(&self, field: &str) -> Option<&str> {
+        match field {
+            "deplio_server" => self.deplio_server.as_deref(),
+            "owner" => self.owner.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, field: &str, value: String) {
+        match field {
+            "deplio_server" => self.deplio_server = Some(value),
+            "owner" => self.owner = Some(value),
+            _ => {}
+        }
+    }
+}
+
+/// A named set of server settings and credentials, declared as `[profiles.<name>]`.
+#[derive(Deserialize, Debug, Default)]
+pub struct Profile {
+    pub deplio_server: Option<String>,
+    pub owner: Option<String>,
+    pub token: Option<String>,
+}
+
+impl Profile {
+    fn get(&self, field: &str) -> Option<&str> {
+        match field {
+            "deplio_server" => self.deplio_server.as_deref(),
+            "owner" => self.owner.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
 pub struct Debug {
     pub synth_working_dir: Option<String>,
     pub override_params: Option<String>,
@@ -118,6 +1013,7 @@ pub struct Debug {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config_versioning::CURRENT_CONFIG_VERSION;
     use std::env;
     use std::fs;
     use tempfile::TempDir;
@@ -129,6 +1025,7 @@ mod tests {
 
         let result = handle_command(&false, &false, Some(temp_home_str));
         assert!(result.is_ok());
+        assert!(result.unwrap().created);
 
         let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
         assert!(config_path.exists());
@@ -149,6 +1046,15 @@ mod tests {
 
         let result = handle_command(&false, &false, Some(temp_home_str));
         assert!(result.is_ok());
+        let result = result.unwrap();
+        assert!(!result.created);
+        assert_eq!(
+            output::render(OutputFormat::Json, &result).unwrap(),
+            format!(
+                "{{\n  \"path\": {:?},\n  \"created\": false\n}}\n",
+                config_path.display().to_string()
+            )
+        );
 
         // Verify existing content is preserved when overwrite is false
         let content = fs::read_to_string(&config_path).expect("Failed to read config");
@@ -186,10 +1092,12 @@ mod tests {
 
         let result = handle_command(&true, &false, Some(temp_home_str));
         assert!(result.is_err());
+        let error = result.unwrap_err();
         assert_eq!(
-            result.unwrap_err(),
+            error.message,
             "EDITOR environment variable not set. Unable to open editor."
         );
+        assert_eq!(error.category, ErrorCategory::Environment);
 
         // Restore original EDITOR if it was set
         if let Ok(editor) = original_editor {
@@ -199,12 +1107,22 @@ mod tests {
         }
     }
 
+    /// An environment with the temp dir as home and nothing from the machine running the tests.
+    fn isolated_environment(temp_dir: &TempDir) -> ConfigEnvironment {
+        ConfigEnvironment {
+            home_dir: temp_dir.path().to_path_buf(),
+            system_path: temp_dir.path().join("etc-deplio"),
+            xdg_config_home: None,
+            current_dir: temp_dir.path().join("project"),
+            vars: Vec::new(),
+        }
+    }
+
     #[test]
     fn test_load_config_with_no_file_returns_default() {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
-        let temp_home_str = temp_dir.path().to_str().unwrap();
 
-        let result = load_config(Some(temp_home_str));
+        let result = load_config_in(&isolated_environment(&temp_dir));
         assert!(result.is_ok());
 
         let config = result.unwrap();
@@ -217,7 +1135,6 @@ mod tests {
     #[test]
     fn test_load_config_with_valid_toml() {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
-        let temp_home_str = temp_dir.path().to_str().unwrap();
         let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
         let valid_toml = r#"
 [defaults]
@@ -231,7 +1148,7 @@ override_params = "--verbose --debug"
 
         fs::write(&config_path, valid_toml).expect("Failed to write config");
 
-        let result = load_config(Some(temp_home_str));
+        let result = load_config_in(&isolated_environment(&temp_dir));
         assert!(result.is_ok());
 
         let config = result.unwrap();
@@ -253,7 +1170,6 @@ override_params = "--verbose --debug"
     #[test]
     fn test_load_config_with_invalid_toml() {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
-        let temp_home_str = temp_dir.path().to_str().unwrap();
         let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
         let invalid_toml = r#"
 [defaults
@@ -262,19 +1178,21 @@ deplio_server = "missing closing bracket"
 
         fs::write(&config_path, invalid_toml).expect("Failed to write config");
 
-        let result = load_config(Some(temp_home_str));
+        let result = load_config_in(&isolated_environment(&temp_dir));
         assert!(result.is_err());
 
         match result.unwrap_err() {
-            ConfigurationError::DeserializationFail(_) => {}
-            _ => panic!("Expected DeserializationFail error"),
+            ConfigurationError::InvalidToml(error, _) => {
+                assert_eq!(error.path, config_path);
+                assert_eq!(error.line_col(), Some((2, 10)));
+            }
+            _ => panic!("Expected InvalidToml error"),
         }
     }
 
     #[test]
     fn test_load_config_with_partial_config() {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
-        let temp_home_str = temp_dir.path().to_str().unwrap();
         let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
         let partial_toml = r#"
 [defaults]
@@ -285,7 +1203,7 @@ owner = "partial-owner"
 
         fs::write(&config_path, partial_toml).expect("Failed to write config");
 
-        let result = load_config(Some(temp_home_str));
+        let result = load_config_in(&isolated_environment(&temp_dir));
         assert!(result.is_ok());
 
         let config = result.unwrap();
@@ -295,6 +1213,730 @@ owner = "partial-owner"
         assert!(config.debug.override_params.is_none());
     }
 
+    #[test]
+    fn test_load_config_with_missing_sections() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+        let partial_toml = r#"
+[defaults]
+owner = "only-defaults"
+"#;
+
+        fs::write(&config_path, partial_toml).expect("Failed to write config");
+
+        let config = load_config_in(&isolated_environment(&temp_dir)).expect("config should load");
+        assert_eq!(config.defaults.owner, Some("only-defaults".to_string()));
+        assert!(config.debug.override_params.is_none());
+    }
+
+    #[test]
+    fn test_resolve_config_later_layers_win() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let system_path = temp_dir.path().join("system");
+        let user_path = temp_dir.path().join("user");
+        let project_path = temp_dir.path().join("project");
+        fs::write(
+            &system_path,
+            "[defaults]\nowner = \"system-owner\"\ndeplio_server = \"https://system\"\n",
+        )
+        .expect("Failed to write system config");
+        fs::write(
+            &user_path,
+            "[defaults]\nowner = \"user-owner\"\n\n[debug]\noverride_params = \"init\"\n",
+        )
+        .expect("Failed to write user config");
+        fs::write(&project_path, "[defaults]\nowner = \"project-owner\"\n")
+            .expect("Failed to write project config");
+
+        let sources = ConfigSources {
+            files: vec![
+                (ConfigLayer::System, system_path.clone()),
+                (ConfigLayer::Xdg, temp_dir.path().join("missing")),
+                (ConfigLayer::User, user_path.clone()),
+                (ConfigLayer::Project, project_path.clone()),
+            ],
+            env: vec![
+                ("DEPLIO_SERVER".to_string(), "https://env".to_string()),
+                ("DEPLIO_UNKNOWN".to_string(), "ignored".to_string()),
+            ],
+        };
+
+        let resolved = resolve_config(&sources).expect("config should resolve");
+        assert_eq!(
+            resolved.config.defaults.owner,
+            Some("project-owner".to_string())
+        );
+        assert_eq!(
+            resolved.config.defaults.deplio_server,
+            Some("https://env".to_string())
+        );
+        assert_eq!(
+            resolved.config.debug.override_params,
+            Some("init".to_string())
+        );
+        assert!(resolved.config.debug.synth_working_dir.is_none());
+
+        let owner_origin = resolved.origin_of("defaults.owner").unwrap();
+        assert_eq!(owner_origin.layer, ConfigLayer::Project);
+        assert_eq!(owner_origin.source, project_path.display().to_string());
+        let server_origin = resolved.origin_of("defaults.deplio_server").unwrap();
+        assert_eq!(server_origin.layer, ConfigLayer::Environment);
+        assert_eq!(server_origin.to_string(), "env (DEPLIO_SERVER)");
+        assert_eq!(
+            resolved.origin_of("debug.override_params").unwrap().source,
+            user_path.display().to_string()
+        );
+        assert!(resolved.origin_of("debug.synth_working_dir").is_none());
+    }
+
+    #[test]
+    fn test_resolve_config_reports_failing_file() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let bad_path = temp_dir.path().join("bad");
+        fs::write(&bad_path, "[defaults\n").expect("Failed to write config");
+
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::System, bad_path.clone())],
+            env: vec![],
+        };
+
+        match resolve_config(&sources).unwrap_err() {
+            ConfigurationError::InvalidToml(error, _) => {
+                assert!(
+                    error
+                        .to_string()
+                        .starts_with(&format!("{}:1:", bad_path.display()))
+                );
+            }
+            _ => panic!("Expected InvalidToml error"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_toml_suggestion_names_the_broken_file() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let project_path = temp_dir.path().join("project");
+        let user_path = temp_dir.path().join("user");
+        fs::write(&project_path, "[defaults\n").expect("Failed to write config");
+        fs::write(&user_path, "[defaults\n").expect("Failed to write config");
+
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::Project, project_path.clone())],
+            env: vec![],
+        };
+        let error: CliError = resolve_config(&sources).unwrap_err().into();
+        assert_eq!(
+            error.suggestion,
+            Some(format!("fix {}", project_path.display()))
+        );
+
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::User, user_path)],
+            env: vec![],
+        };
+        let error: CliError = resolve_config(&sources).unwrap_err().into();
+        assert!(
+            error
+                .suggestion
+                .unwrap()
+                .contains("`deplio config --overwrite`")
+        );
+    }
+
+    #[test]
+    fn test_resolve_config_reports_type_error_location() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let user_path = temp_dir.path().join("user");
+        fs::write(&user_path, "[defaults]\nowner = 5\n").expect("Failed to write config");
+
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::User, user_path.clone())],
+            env: vec![],
+        };
+
+        let error: CliError = resolve_config(&sources).unwrap_err().into();
+        assert_eq!(error.category, ErrorCategory::Config);
+        assert_eq!(error.exit_code(), 78);
+        assert!(error.to_string().contains("2 | owner = 5\n  |         ^"));
+        assert!(error.suggestion.is_some());
+    }
+
+    #[test]
+    fn test_set_value_creates_file_from_template() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+
+        set_value(&config_path, "defaults.owner", "acme").expect("set should succeed");
+
+        let content = fs::read_to_string(&config_path).expect("Failed to read config");
+        assert!(content.contains("### Deplio configuration file ###"));
+        assert!(content.contains("# deplio_server = \"https://your.deplio.server\""));
+        let config: Configuration = toml::from_str(&content).expect("config should parse");
+        assert_eq!(config.defaults.owner, Some("acme".to_string()));
+    }
+
+    #[test]
+    fn test_set_value_replaces_existing_value_and_keeps_comments() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+        fs::write(
+            &config_path,
+            "# my notes\n[defaults]\nowner = \"old\" # trailing\n",
+        )
+        .expect("Failed to write config");
+
+        set_value(&config_path, "defaults.owner", "new").expect("set should succeed");
+        set_value(&config_path, "debug.override_params", "init -a x").expect("set should succeed");
+
+        let content = fs::read_to_string(&config_path).expect("Failed to read config");
+        assert!(content.starts_with("# my notes\n"));
+        assert!(!content.contains("\"old\""));
+        let config: Configuration = toml::from_str(&content).expect("config should parse");
+        assert_eq!(config.defaults.owner, Some("new".to_string()));
+        assert_eq!(config.debug.override_params, Some("init -a x".to_string()));
+    }
+
+    #[test]
+    fn test_set_result_masks_secrets() {
+        let path = PathBuf::from("/home/me/.deplio");
+        let result = SetResult::new("defaults.owner", "acme", path.clone());
+        assert!(result.render_text().is_empty());
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(
+                &output::render(OutputFormat::Json, &result).unwrap()
+            )
+            .unwrap(),
+            serde_json::json!({
+                "key": "defaults.owner",
+                "value": "acme",
+                "masked": false,
+                "path": "/home/me/.deplio"
+            })
+        );
+
+        let result = SetResult::new("profiles.prod.token", "s3cr3t", path);
+        assert!(result.masked);
+        assert_eq!(result.value, MASKED_VALUE);
+    }
+
+    #[test]
+    fn test_set_value_rejects_unknown_key() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+
+        let error = set_value(&config_path, "defaults.nope", "value").unwrap_err();
+        assert_eq!(error.message, "Unknown configuration key defaults.nope");
+        assert_eq!(error.category, ErrorCategory::Usage);
+        assert!(!config_path.exists());
+    }
+
+    #[test]
+    fn test_unset_value() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+        fs::write(
+            &config_path,
+            "[defaults]\n# keep me\nowner = \"acme\"\ndeplio_server = \"https://deplio\"\n",
+        )
+        .expect("Failed to write config");
+
+        assert!(unset_value(&config_path, "defaults.owner").unwrap());
+        assert!(!unset_value(&config_path, "defaults.owner").unwrap());
+
+        let content = fs::read_to_string(&config_path).expect("Failed to read config");
+        let config: Configuration = toml::from_str(&content).expect("config should parse");
+        assert!(config.defaults.owner.is_none());
+        assert_eq!(
+            config.defaults.deplio_server,
+            Some("https://deplio".to_string())
+        );
+    }
+
+    #[test]
+    fn test_list_values_with_origin() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let user_path = temp_dir.path().join("user");
+        fs::write(&user_path, "[defaults]\nowner = \"acme\"\n").expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::User, user_path.clone())],
+            env: vec![("DEPLIO_SERVER".to_string(), "https://env".to_string())],
+        };
+        let resolved = resolve_config(&sources).expect("config should resolve");
+
+        assert_eq!(
+            list_values(&resolved, false).render_text(),
+            vec![
+                "defaults.deplio_server = \"https://env\"".to_string(),
+                "defaults.owner = \"acme\"".to_string(),
+            ]
+        );
+        assert_eq!(
+            list_values(&resolved, true).render_text(),
+            vec![
+                "env (DEPLIO_SERVER)\tdefaults.deplio_server = \"https://env\"".to_string(),
+                format!("user ({})\tdefaults.owner = \"acme\"", user_path.display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_secrets_are_masked() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let user_path = temp_dir.path().join("user");
+        fs::write(
+            &user_path,
+            "[profiles.prod]\ntoken = \"s3cr3t\"\nowner = \"acme\"\n",
+        )
+        .expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::User, user_path)],
+            env: vec![],
+        };
+        let resolved = resolve_config(&sources).expect("config should resolve");
+
+        let list = list_values(&resolved, false);
+        assert_eq!(
+            list.render_text(),
+            vec![
+                "profiles.prod.owner = \"acme\"".to_string(),
+                "profiles.prod.token = \"********\"".to_string(),
+            ]
+        );
+        for format in [OutputFormat::Json, OutputFormat::Yaml] {
+            assert!(!output::render(format, &list).unwrap().contains("s3cr3t"));
+        }
+
+        let token = &resolved.values["profiles.prod.token"];
+        let masked = ConfigEntry::new("profiles.prod.token", token, false);
+        assert!(masked.masked);
+        assert_eq!(masked.render_text(), vec![MASKED_VALUE]);
+        let shown = ConfigEntry::new("profiles.prod.token", token, true);
+        assert!(!shown.masked);
+        assert_eq!(shown.render_text(), vec!["s3cr3t"]);
+    }
+
+    #[test]
+    fn test_list_values_json() {
+        let sources = ConfigSources {
+            files: vec![],
+            env: vec![("DEPLIO_OWNER".to_string(), "acme".to_string())],
+        };
+        let resolved = resolve_config(&sources).expect("config should resolve");
+
+        let json = output::render(OutputFormat::Json, &list_values(&resolved, false)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({
+                "values": [{
+                    "key": "defaults.owner",
+                    "value": "acme",
+                    "masked": false,
+                    "origin": { "layer": "env", "source": "DEPLIO_OWNER" }
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_defaults_source_follows_active_profile() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let user_path = temp_dir.path().join("user");
+        fs::write(
+            &user_path,
+            "[defaults]\nowner = \"acme\"\n\n[profiles.prod]\nowner = \"prod-team\"\n",
+        )
+        .expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::User, user_path.clone())],
+            env: vec![("DEPLIO_SERVER".to_string(), "https://env".to_string())],
+        };
+        let mut config = resolve_config(&sources)
+            .expect("config should resolve")
+            .config;
+
+        assert_eq!(
+            config.defaults_source("owner"),
+            format!("defaults.owner from user ({})", user_path.display())
+        );
+        config.select_profile(Some("prod")).unwrap();
+        assert_eq!(
+            config.defaults_source("owner"),
+            format!("profiles.prod.owner from user ({})", user_path.display())
+        );
+        assert_eq!(
+            config.defaults_source("deplio_server"),
+            "defaults.deplio_server from env (DEPLIO_SERVER)"
+        );
+        assert_eq!(
+            Configuration::default().defaults_source("owner"),
+            "defaults.owner"
+        );
+    }
+
+    #[test]
+    fn test_environment_overrides_win_over_profile() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let user_path = temp_dir.path().join("user");
+        fs::write(
+            &user_path,
+            "default_profile = \"prod\"\n\n[profiles.prod]\nowner = \"prod-team\"\ndeplio_server = \"https://prod\"\n",
+        )
+        .expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::User, user_path.clone())],
+            env: vec![("DEPLIO_OWNER".to_string(), "env-owner".to_string())],
+        };
+        let mut resolved = resolve_config(&sources).expect("config should resolve");
+        resolved.select_profile(None).expect("profile should apply");
+
+        assert_eq!(
+            resolved.config.defaults.owner,
+            Some("env-owner".to_string())
+        );
+        assert_eq!(
+            resolved.config.defaults.deplio_server,
+            Some("https://prod".to_string())
+        );
+        // config get and list show what commands use
+        assert_eq!(
+            resolved.get("defaults.owner"),
+            Some(&Value::String("env-owner".to_string()))
+        );
+        assert_eq!(
+            resolved.get("defaults.deplio_server"),
+            Some(&Value::String("https://prod".to_string()))
+        );
+        assert_eq!(
+            resolved
+                .origin_of("defaults.deplio_server")
+                .unwrap()
+                .to_string(),
+            format!("user ({}) via profile prod", user_path.display())
+        );
+        assert_eq!(
+            resolved.config.defaults_source("owner"),
+            "defaults.owner from env (DEPLIO_OWNER)"
+        );
+    }
+
+    #[test]
+    fn test_select_profile_overrides_defaults() {
+        let mut config: Configuration = toml::from_str(
+            r#"
+default_profile = "internal"
+
+[defaults]
+deplio_server = "https://default"
+owner = "default-owner"
+
+[profiles.internal]
+deplio_server = "https://internal"
+token = "internal-token"
+
+[profiles.staging]
+owner = "staging-owner"
+"#,
+        )
+        .expect("config should parse");
+
+        config
+            .select_profile(None)
+            .expect("default profile should apply");
+        assert_eq!(config.active_profile, Some("internal".to_string()));
+        assert_eq!(
+            config.defaults.deplio_server,
+            Some("https://internal".to_string())
+        );
+        assert_eq!(config.defaults.owner, Some("default-owner".to_string()));
+        assert_eq!(config.token(), Some("internal-token"));
+    }
+
+    #[test]
+    fn test_select_profile_flag_wins_over_default_profile() {
+        let mut config: Configuration = toml::from_str(
+            r#"
+default_profile = "internal"
+
+[profiles.internal]
+owner = "internal-owner"
+
+[profiles.staging]
+owner = "staging-owner"
+"#,
+        )
+        .expect("config should parse");
+
+        config
+            .select_profile(Some("staging"))
+            .expect("profile should apply");
+        assert_eq!(config.defaults.owner, Some("staging-owner".to_string()));
+        assert!(config.token().is_none());
+    }
+
+    #[test]
+    fn test_token_is_only_paired_with_profile_server() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let user_path = temp_dir.path().join("user");
+        let project_path = temp_dir.path().join("project");
+        fs::write(
+            &user_path,
+            "default_profile = \"prod\"\n\n[profiles.prod]\ntoken = \"s3cr3t\"\n",
+        )
+        .expect("Failed to write config");
+        fs::write(
+            &project_path,
+            "[defaults]\ndeplio_server = \"https://evil.example\"\n",
+        )
+        .expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![
+                (ConfigLayer::User, user_path.clone()),
+                (ConfigLayer::Project, project_path),
+            ],
+            env: vec![],
+        };
+
+        let mut config = resolve_config(&sources)
+            .expect("config should resolve")
+            .config;
+        config.select_profile(None).expect("profile should apply");
+        assert_eq!(
+            config.defaults.deplio_server,
+            Some("https://evil.example".to_string())
+        );
+        assert!(config.token().is_none());
+
+        // an environment override replacing the profile's server doesn't get the token either
+        fs::write(
+            &user_path,
+            "default_profile = \"prod\"\n\n[profiles.prod]\ndeplio_server = \"https://prod\"\ntoken = \"s3cr3t\"\n",
+        )
+        .expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::User, user_path)],
+            env: vec![("DEPLIO_SERVER".to_string(), "https://env".to_string())],
+        };
+        let mut config = resolve_config(&sources)
+            .expect("config should resolve")
+            .config;
+        config.select_profile(None).expect("profile should apply");
+        assert!(config.token().is_none());
+    }
+
+    #[test]
+    fn test_select_profile_unknown_profile() {
+        let mut config: Configuration =
+            toml::from_str("[profiles.internal]\n").expect("config should parse");
+
+        let error = config.select_profile(Some("prod")).unwrap_err();
+        assert_eq!(error.message, "Profile prod not found");
+        assert_eq!(
+            error.suggestion,
+            Some("available profiles: internal".to_string())
+        );
+        assert!(config.active_profile.is_none());
+    }
+
+    #[test]
+    fn test_select_profile_without_profiles_is_noop() {
+        let mut config = Configuration::default();
+        assert!(config.select_profile(None).is_ok());
+        assert!(config.active_profile.is_none());
+    }
+
+    #[test]
+    fn test_set_value_profile_key() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+
+        set_value(&config_path, "profiles.internal.token", "secret").expect("set should succeed");
+        set_value(&config_path, "default_profile", "internal").expect("set should succeed");
+
+        let content = fs::read_to_string(&config_path).expect("Failed to read config");
+        assert!(content.starts_with("### Deplio configuration file ###"));
+        assert!(content.contains("[profiles.internal]"));
+        assert!(!content.contains("[profiles]\n"));
+        // the template's closing comments stay above the new table
+        assert!(
+            content.find("# token = ").unwrap() < content.find("\n[profiles.internal]").unwrap()
+        );
+        let config: Configuration = toml::from_str(&content).expect("config should parse");
+        assert_eq!(config.default_profile, Some("internal".to_string()));
+        assert_eq!(
+            config.profiles["internal"].token,
+            Some("secret".to_string())
+        );
+
+        assert!(unset_value(&config_path, "profiles.internal.token").unwrap());
+        assert!(!unset_value(&config_path, "profiles.missing.token").unwrap());
+        assert!(
+            set_value(&config_path, "profiles.internal.nope", "x")
+                .unwrap_err()
+                .message
+                .contains("Unknown configuration key")
+        );
+    }
+
+    #[test]
+    fn test_in_environment_uses_home_for_xdg_fallback() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let mut environment = isolated_environment(&temp_dir);
+        environment.vars = vec![
+            ("DEPLIO_OWNER".to_string(), "acme".to_string()),
+            ("HOME".to_string(), "/elsewhere".to_string()),
+        ];
+
+        let sources = ConfigSources::in_environment(&environment);
+        assert_eq!(
+            sources.files[1],
+            (
+                ConfigLayer::Xdg,
+                temp_dir.path().join(".config/deplio/config.toml")
+            )
+        );
+        assert_eq!(
+            sources.env,
+            vec![("DEPLIO_OWNER".to_string(), "acme".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_from_process_home_override_ignores_xdg_config_home() {
+        let environment =
+            ConfigEnvironment::from_process(Some("/tmp/home")).expect("environment should load");
+        assert_eq!(environment.xdg_config_home, None);
+        assert_eq!(environment.home_dir, PathBuf::from("/tmp/home"));
+    }
+
+    #[test]
+    fn test_project_layer_cannot_set_restricted_keys() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let project_path = temp_dir.path().join("project");
+        fs::write(
+            &project_path,
+            r#"
+default_profile = "prod"
+
+[defaults]
+owner = "project-owner"
+
+[debug]
+override_params = "config set profiles.prod.deplio_server https://evil.example"
+synth_working_dir = "/"
+
+[profiles.prod]
+deplio_server = "https://evil.example"
+"#,
+        )
+        .expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![(ConfigLayer::Project, project_path)],
+            env: vec![],
+        };
+
+        let resolved = resolve_config(&sources).expect("config should resolve");
+        assert_eq!(
+            resolved.config.defaults.owner,
+            Some("project-owner".to_string())
+        );
+        assert!(resolved.config.debug.override_params.is_none());
+        assert!(resolved.config.debug.synth_working_dir.is_none());
+        assert!(resolved.config.default_profile.is_none());
+        assert!(resolved.config.profiles.is_empty());
+        assert_eq!(
+            resolved.values.keys().collect::<Vec<_>>(),
+            vec!["defaults.owner"]
+        );
+    }
+
+    #[test]
+    fn test_only_user_layer_can_set_debug() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let system_path = temp_dir.path().join("system");
+        let user_path = temp_dir.path().join("user");
+        fs::write(
+            &system_path,
+            "[debug]\noverride_params = \"init\"\n\n[profiles.prod]\nowner = \"acme\"\n",
+        )
+        .expect("Failed to write config");
+        fs::write(&user_path, "[debug]\nsynth_working_dir = \"/tmp\"\n")
+            .expect("Failed to write config");
+        let sources = ConfigSources {
+            files: vec![
+                (ConfigLayer::System, system_path),
+                (ConfigLayer::User, user_path),
+            ],
+            env: vec![],
+        };
+
+        let resolved = resolve_config(&sources).expect("config should resolve");
+        assert!(resolved.config.debug.override_params.is_none());
+        assert_eq!(
+            resolved.config.debug.synth_working_dir,
+            Some("/tmp".to_string())
+        );
+        assert!(resolved.config.profiles.contains_key("prod"));
+    }
+
+    #[test]
+    fn test_resolve_config_profile_from_env() {
+        let sources = ConfigSources {
+            files: vec![],
+            env: vec![("DEPLIO_PROFILE".to_string(), "staging".to_string())],
+        };
+
+        let resolved = resolve_config(&sources).expect("config should resolve");
+        assert_eq!(resolved.config.default_profile, Some("staging".to_string()));
+    }
+
+    #[test]
+    fn test_load_config_upgrades_unversioned_file() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+        fs::write(&config_path, "[defaults]\nowner = \"legacy\"\n")
+            .expect("Failed to write config");
+
+        let config = load_config_in(&isolated_environment(&temp_dir)).expect("config should load");
+        assert_eq!(config.defaults.owner, Some("legacy".to_string()));
+
+        // loading doesn't rewrite the file
+        let content = fs::read_to_string(&config_path).expect("Failed to read config");
+        assert!(!content.contains("version"));
+    }
+
+    #[test]
+    fn test_load_config_with_newer_version() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+        fs::write(&config_path, "version = \"99.0.0\"\n").expect("Failed to write config");
+
+        match load_config_in(&isolated_environment(&temp_dir)).unwrap_err() {
+            ConfigurationError::UpgradeFail(message) => {
+                assert!(message.contains("Downgrades are not supported"));
+            }
+            _ => panic!("Expected UpgradeFail error"),
+        }
+    }
+
+    #[test]
+    fn test_set_value_upgrades_unversioned_file() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let config_path = temp_dir.path().join(DEPLIO_CONFIG_FILE_NAME);
+        fs::write(&config_path, "[defaults]\nowner = \"legacy\"\n")
+            .expect("Failed to write config");
+
+        set_value(&config_path, "defaults.owner", "acme").expect("set should succeed");
+
+        let content = fs::read_to_string(&config_path).expect("Failed to read config");
+        assert_eq!(
+            content,
+            format!(
+                "version = \"{}\"\n\n[defaults]\nowner = \"acme\"\n",
+                CURRENT_CONFIG_VERSION
+            )
+        );
+    }
+
     #[test]
     fn test_configuration_error_debug_display() {
         let errors = vec![
@@ -302,6 +1944,11 @@ owner = "partial-owner"
             ConfigurationError::IoFail("IO error".to_string()),
             ConfigurationError::FileReadFail("Read error".to_string()),
             ConfigurationError::DeserializationFail("Parse error".to_string()),
+            ConfigurationError::InvalidToml(
+                TomlSourceError::new(".deplio", "", None, "TOML error"),
+                ConfigLayer::User,
+            ),
+            ConfigurationError::UpgradeFail("Upgrade error".to_string()),
         ];
 
         // Test that all error variants can be formatted for debugging
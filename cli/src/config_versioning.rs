@@ -0,0 +1,145 @@
+use lib::versioning::{self, UpgradeError, Upgrader};
+use toml::{Table, Value};
+use toml_edit::DocumentMut;
+use tracing::debug;
+
+/// The version of the user configuration format written by this release.
+pub static CURRENT_CONFIG_VERSION: &str = "1.0.0";
+
+/// The version assumed for configuration files written before the `version` key existed.
+pub static UNVERSIONED_CONFIG_VERSION: &str = "0.1.0";
+
+static UPGRADERS: &[&dyn Upgrader] = &[&AddVersionKey];
+
+/// Upgrades the contents of a user configuration file to [`CURRENT_CONFIG_VERSION`].
+/// Contents that aren't valid TOML are returned as-is, for the caller to report.
+pub fn upgrade_config(contents: &str) -> Result<String, UpgradeError> {
+    let Ok(table) = toml::from_str::<Table>(contents) else {
+        return Ok(contents.to_string());
+    };
+    let from_version = match table.get("version") {
+        Some(Value::String(version)) => version.as_str(),
+        Some(value) => {
+            return Err(UpgradeError::InvalidVersionFormat(format!(
+                "version must be a string, found {}",
+                value
+            )));
+        }
+        None => UNVERSIONED_CONFIG_VERSION,
+    };
+    debug!(version = from_version, "configuration file version");
+    versioning::upgrade_data(from_version, CURRENT_CONFIG_VERSION, contents, UPGRADERS)
+}
+
+/// 0.1.0 -> 1.0.0: adds the `version` key.
+struct AddVersionKey;
+
+impl Upgrader for AddVersionKey {
+    fn upgrade(&self, data: &str) -> String {
+        let Ok(mut document) = data.parse::<DocumentMut>() else {
+            return data.to_string();
+        };
+        insert_root_value(&mut document, "version", self.version());
+        document.to_string()
+    }
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    fn can_upgrade(&self, version: &str) -> bool {
+        version == UNVERSIONED_CONFIG_VERSION
+    }
+
+    fn breaking_change_message(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Inserts a new top level value. New root keys are written above the first table, so the
+/// comment block leading into that table (the file header, in the template) is moved above
+/// the new key to keep it at the top of the file.
+pub(crate) fn insert_root_value(document: &mut DocumentMut, field: &str, value: &str) {
+    let has_root_values = document.iter().any(|(_, item)| item.is_value());
+    let first_table = document
+        .iter_mut()
+        .filter_map(|(_, item)| item.as_table_mut())
+        .min_by_key(|table| table.position().unwrap_or(usize::MAX));
+    let header = match first_table {
+        Some(table) if !has_root_values => {
+            let header = table
+                .decor()
+                .prefix()
+                .and_then(|prefix| prefix.as_str())
+                .map(str::to_string);
+            table.decor_mut().set_prefix("\n");
+            header
+        }
+        _ => None,
+    };
+
+    document.insert(field, toml_edit::value(value));
+    if let (Some(header), Some(mut key)) = (header, document.key_mut(field)) {
+        key.leaf_decor_mut().set_prefix(header);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::CONFIG_TEMPLATE;
+
+    #[test]
+    fn test_template_is_current_version() {
+        let table: Table = toml::from_str(CONFIG_TEMPLATE).expect("template should parse");
+        assert_eq!(
+            table.get("version").and_then(Value::as_str),
+            Some(CURRENT_CONFIG_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_upgrade_config_current_version_is_unchanged() {
+        let result = upgrade_config(CONFIG_TEMPLATE).expect("upgrade should succeed");
+        assert_eq!(result, CONFIG_TEMPLATE);
+    }
+
+    #[test]
+    fn test_upgrade_config_adds_version_to_unversioned_file() {
+        let contents = "# my notes\n[defaults]\nowner = \"acme\"\n";
+
+        let result = upgrade_config(contents).expect("upgrade should succeed");
+
+        assert_eq!(
+            result,
+            "# my notes\nversion = \"1.0.0\"\n\n[defaults]\nowner = \"acme\"\n"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_config_rejects_newer_version() {
+        let result = upgrade_config("version = \"99.0.0\"\n");
+        match result.unwrap_err() {
+            UpgradeError::DowngradeNotSupported { from, to } => {
+                assert_eq!(from, "99.0.0");
+                assert_eq!(to, CURRENT_CONFIG_VERSION);
+            }
+            _ => panic!("Expected DowngradeNotSupported error"),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_config_rejects_non_string_version() {
+        let result = upgrade_config("version = 1\n");
+        match result.unwrap_err() {
+            UpgradeError::InvalidVersionFormat(_) => {}
+            _ => panic!("Expected InvalidVersionFormat error"),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_config_leaves_invalid_toml_alone() {
+        let contents = "[defaults\n";
+        assert_eq!(upgrade_config(contents).unwrap(), contents);
+    }
+}
@@ -0,0 +1,284 @@
+use serde::Serialize;
+use std::{fmt, ops::Range, path::PathBuf};
+use thiserror::Error;
+
+/// Broad categories of CLI failures, each with its own process exit code.
+/// The codes follow the BSD `sysexits.h` conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCategory {
+    /// The command was called incorrectly, e.g. an unknown configuration key.
+    Usage,
+    /// The project's files, such as `deplio.toml`, are invalid.
+    Project,
+    /// Something the CLI needs from the environment is missing, e.g. `$EDITOR`.
+    Environment,
+    /// Reading or writing a file failed.
+    Io,
+    /// The user configuration is invalid.
+    Config,
+}
+
+impl ErrorCategory {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorCategory::Usage => 64,
+            ErrorCategory::Project => 65,
+            ErrorCategory::Environment => 69,
+            ErrorCategory::Io => 74,
+            ErrorCategory::Config => 78,
+        }
+    }
+}
+
+/// A TOML error located in a file, rendered with the offending source line.
+#[derive(Debug, Clone, Error)]
+#[error("{}: {message}", self.location())]
+pub struct TomlSourceError {
+    pub path: PathBuf,
+    pub contents: String,
+    pub span: Option<Range<usize>>,
+    pub message: String,
+}
+
+impl TomlSourceError {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        contents: &str,
+        span: Option<Range<usize>>,
+        message: &str,
+    ) -> Self {
+        TomlSourceError {
+            path: path.into(),
+            contents: contents.to_string(),
+            span,
+            message: message.trim_end().replace('\n', ", "),
+        }
+    }
+
+    /// The one based line and column of the start of the span.
+    pub fn line_col(&self) -> Option<(usize, usize)> {
+        let start = self.span.as_ref()?.start.min(self.contents.len());
+        let before = &self.contents[..start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Some((line, before[line_start..].chars().count() + 1))
+    }
+
+    fn location(&self) -> String {
+        match self.line_col() {
+            Some((line, col)) => format!("{}:{}:{}", self.path.display(), line, col),
+            None => self.path.display().to_string(),
+        }
+    }
+
+    /// Renders the source line the error points at, with the span underlined.
+    pub fn render_snippet(&self) -> Option<String> {
+        let span = self.span.as_ref()?;
+        let (line, col) = self.line_col()?;
+        let source_line = self.contents.lines().nth(line - 1).unwrap_or_default();
+        let line_len = source_line.chars().count();
+        let width = self.contents
+            [span.start.min(self.contents.len())..span.end.min(self.contents.len())]
+            .chars()
+            .take_while(|c| *c != '\n')
+            .count()
+            .clamp(1, (line_len + 1).saturating_sub(col).max(1));
+        let gutter = " ".repeat(line.to_string().len());
+
+        Some(format!(
+            "{gutter}--> {}\n{gutter} |\n{line} | {source_line}\n{gutter} | {}{}",
+            self.location(),
+            " ".repeat(col - 1),
+            "^".repeat(width),
+        ))
+    }
+}
+
+/// The error type for every CLI command.
+///
+/// Errors carry a category deciding the exit code, a chain of context messages added as the
+/// error travels up, and optionally the TOML source it points at and a suggested fix.
+#[derive(Debug)]
+pub struct CliError {
+    pub category: ErrorCategory,
+    pub message: String,
+    /// Context added by callers, innermost first.
+    pub context: Vec<String>,
+    pub source: Option<Box<TomlSourceError>>,
+    pub suggestion: Option<String>,
+}
+
+impl CliError {
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        CliError {
+            category,
+            message: message.into(),
+            context: Vec::new(),
+            source: None,
+            suggestion: None,
+        }
+    }
+
+    pub fn usage(message: impl Into<String>) -> Self {
+        CliError::new(ErrorCategory::Usage, message)
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        CliError::new(ErrorCategory::Io, message)
+    }
+
+    /// Builds an error for an invalid TOML file in the given category.
+    pub fn toml(category: ErrorCategory, source: TomlSourceError) -> Self {
+        CliError {
+            message: source.to_string(),
+            source: Some(Box::new(source)),
+            ..CliError::new(category, "")
+        }
+    }
+
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context.push(context.into());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.category.exit_code()
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chain = self.context.iter().rev();
+        match chain.next() {
+            Some(outermost) => {
+                write!(f, "Error: {}", outermost)?;
+                for context in chain {
+                    write!(f, "\n  caused by: {}", context)?;
+                }
+                write!(f, "\n  caused by: {}", self.message)?;
+            }
+            None => write!(f, "Error: {}", self.message)?,
+        }
+        if let Some(snippet) = self
+            .source
+            .as_ref()
+            .and_then(|source| source.render_snippet())
+        {
+            write!(f, "\n{}", snippet)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\nhelp: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Adds context to the error of a result, converting it into a [`CliError`].
+pub trait Context<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, CliError>;
+}
+
+impl<T, E: Into<CliError>> Context<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, CliError> {
+        self.map_err(|e| e.into().with_context(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_error(contents: &str, span: Range<usize>) -> TomlSourceError {
+        TomlSourceError::new("/home/user/.deplio", contents, Some(span), "invalid string")
+    }
+
+    #[test]
+    fn test_line_col() {
+        let error = source_error("[defaults]\nowner = acme\n", 19..23);
+        assert_eq!(error.line_col(), Some((2, 9)));
+        assert_eq!(error.to_string(), "/home/user/.deplio:2:9: invalid string");
+    }
+
+    #[test]
+    fn test_render_snippet() {
+        let error = source_error("[defaults]\nowner = acme\n", 19..23);
+        assert_eq!(
+            error.render_snippet().unwrap(),
+            " --> /home/user/.deplio:2:9\n  |\n2 | owner = acme\n  |         ^^^^"
+        );
+    }
+
+    #[test]
+    fn test_render_snippet_span_past_end_of_line() {
+        let error = source_error("[defaults\n", 9..10);
+        assert_eq!(
+            error.render_snippet().unwrap(),
+            " --> /home/user/.deplio:1:10\n  |\n1 | [defaults\n  |          ^"
+        );
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let error = TomlSourceError::new("deplio.toml", "", None, "missing field `app`");
+        assert!(error.render_snippet().is_none());
+        assert_eq!(error.to_string(), "deplio.toml: missing field `app`");
+    }
+
+    #[test]
+    fn test_display_with_context_and_suggestion() {
+        let error = CliError::toml(
+            ErrorCategory::Config,
+            source_error("[defaults]\nowner = acme\n", 19..23),
+        )
+        .with_context("Unable to resolve configuration layers")
+        .with_context("Unable to load configuration")
+        .with_suggestion("quote the value");
+
+        assert_eq!(
+            error.to_string(),
+            "Error: Unable to load configuration
+  caused by: Unable to resolve configuration layers
+  caused by: /home/user/.deplio:2:9: invalid string
+ --> /home/user/.deplio:2:9
+  |
+2 | owner = acme
+  |         ^^^^
+help: quote the value"
+        );
+    }
+
+    #[test]
+    fn test_context_converts_errors() {
+        let result: Result<(), CliError> = Err(CliError::io("disk full"));
+        let error = result.context("Unable to write deplio.toml").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error: Unable to write deplio.toml\n  caused by: disk full"
+        );
+        assert_eq!(error.exit_code(), 74);
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let categories = [
+            ErrorCategory::Usage,
+            ErrorCategory::Project,
+            ErrorCategory::Environment,
+            ErrorCategory::Io,
+            ErrorCategory::Config,
+        ];
+        let mut codes: Vec<i32> = categories.iter().map(ErrorCategory::exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), categories.len());
+        assert!(!codes.contains(&0) && !codes.contains(&1) && !codes.contains(&2));
+    }
+}
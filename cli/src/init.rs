@@ -1,33 +1,276 @@
-use text_io::read;
+use crate::config::Configuration;
+use crate::error::{CliError, ErrorCategory};
+use crate::output::Render;
+use crate::prompt::Prompter;
+use crate::validation::validate_dns_label;
+use serde::Serialize;
 
-use crate::config;
+const MAX_OWNER_LENGTH: usize = 100;
 
 pub fn handle_command(
     app_name: &Option<String>,
     owner: &Option<String>,
-) -> Result<(), &'static str> {
-    let app_name = app_name.clone().unwrap_or_else(|| {
-        // no value and no config means we need to prompt for the value
-        print!("Application Name: ");
-        let name: String = read!("{}\n");
-        name
-    });
-
-    let config = config::load_config(None)
-        .expect("No configuration found, please run config command to setup system first.");
-
-    let owner = owner.clone().unwrap_or_else(|| {
-        if let Some(default_owner) = config.defaults.owner {
-            return default_owner;
+    config: &Configuration,
+    prompter: &mut dyn Prompter,
+) -> Result<InitSummary, CliError> {
+    // collect every missing value before failing, so non-interactive runs report them all
+    let mut missing = Vec::new();
+
+    let app_name = resolve_value(
+        app_name
+            .as_deref()
+            .map(|name| Provided::argument(name, "--app-name")),
+        "Application Name",
+        "--app-name",
+        validate_app_name,
+        prompter,
+        &mut missing,
+    )?;
+    let provided_owner = match (owner.as_deref(), config.defaults.owner.as_deref()) {
+        (Some(owner), _) => Some(Provided::argument(owner, "--owner")),
+        (None, Some(owner)) => Some(Provided {
+            value: owner,
+            source: config.defaults_source("owner"),
+            category: ErrorCategory::Config,
+        }),
+        (None, None) => None,
+    };
+    let owner = resolve_value(
+        provided_owner,
+        "Owner Name",
+        "--owner",
+        validate_owner,
+        prompter,
+        &mut missing,
+    )?;
+
+    let (Some(app_name), Some(owner)) = (app_name, owner) else {
+        return Err(
+            CliError::usage(format!("Missing required values: {}", missing.join(", ")))
+                .with_suggestion("pass them as arguments, or run interactively to be prompted"),
+        );
+    };
+
+    Ok(InitSummary { app_name, owner })
+}
+
+/// The values a project was initialized with.
+#[derive(Debug, Serialize)]
+pub struct InitSummary {
+    pub app_name: String,
+    pub owner: String,
+}
+
+impl Render for InitSummary {
+    fn render_text(&self) -> Vec<String> {
+        vec![
+            format!("app_name: {}", self.app_name),
+            format!("owner: {}", self.owner),
+        ]
+    }
+}
+
+/// A value given as an argument or read from the configuration rather than prompted for.
+struct Provided<'a> {
+    value: &'a str,
+    /// Where the value was set, an argument or a configuration key, for errors.
+    source: String,
+    /// Invalid arguments are usage errors, invalid configured values configuration errors.
+    category: ErrorCategory,
+}
+
+impl<'a> Provided<'a> {
+    fn argument(value: &'a str, flag: &str) -> Self {
+        Provided {
+            value,
+            source: flag.to_string(),
+            category: ErrorCategory::Usage,
         }
+    }
+}
 
-        // no value and no config means we need to prompt for the value
-        print!("Owner Name: ");
-        let owner: String = read!("{}\n");
-        owner
-    });
+/// Uses the provided value if there is one, otherwise prompts until a valid value is given.
+/// Adds the value to `missing` when no answer could be prompted for.
+fn resolve_value(
+    provided: Option<Provided>,
+    label: &str,
+    flag: &str,
+    validate: fn(&str) -> Result<(), String>,
+    prompter: &mut dyn Prompter,
+    missing: &mut Vec<String>,
+) -> Result<Option<String>, CliError> {
+    if let Some(provided) = provided {
+        validate(provided.value).map_err(|e| {
+            CliError::new(
+                provided.category,
+                format!("Invalid value for {}: {}", provided.source, e),
+            )
+        })?;
+        return Ok(Some(provided.value.to_string()));
+    }
 
-    println!("app_name: {}", app_name);
-    println!("owner: {}", owner);
+    loop {
+        let Some(answer) = prompter.prompt(label)? else {
+            missing.push(format!("{} ({})", label, flag));
+            return Ok(None);
+        };
+        match validate(&answer) {
+            Ok(()) => return Ok(Some(answer)),
+            Err(e) => prompter.warn(&format!("Invalid {}: {}", label.to_lowercase(), e)),
+        }
+    }
+}
+
+/// App names are used in chart releases and URLs, so they follow DNS label rules.
+pub fn validate_app_name(app_name: &str) -> Result<(), String> {
+    validate_dns_label(app_name)
+}
+
+pub fn validate_owner(owner: &str) -> Result<(), String> {
+    if owner.is_empty() {
+        return Err("the owner can't be empty".to_string());
+    }
+    if owner.chars().count() > MAX_OWNER_LENGTH {
+        return Err(format!(
+            "the owner can't be longer than {} characters",
+            MAX_OWNER_LENGTH
+        ));
+    }
+    if owner.chars().any(char::is_whitespace) {
+        return Err("the owner can't contain whitespace".to_string());
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigLayer, ConfigOrigin};
+    use crate::prompt::{NonInteractivePrompter, ScriptedPrompter};
+
+    fn config_with_owner(owner: Option<&str>) -> Configuration {
+        let mut config = Configuration::default();
+        config.defaults.owner = owner.map(str::to_string);
+        config
+    }
+
+    #[test]
+    fn test_handle_command_with_all_values_does_not_prompt() {
+        let mut prompter = ScriptedPrompter::new(&[]);
+        let result = handle_command(
+            &Some("my-app".to_string()),
+            &Some("acme".to_string()),
+            &config_with_owner(None),
+            &mut prompter,
+        );
+
+        assert!(result.is_ok());
+        assert!(prompter.prompts.is_empty());
+    }
+
+    #[test]
+    fn test_handle_command_prompts_for_missing_values() {
+        let mut prompter = ScriptedPrompter::new(&["my-app", "acme"]);
+        let result = handle_command(&None, &None, &config_with_owner(None), &mut prompter);
+
+        assert!(result.is_ok());
+        assert_eq!(prompter.prompts, vec!["Application Name", "Owner Name"]);
+    }
+
+    #[test]
+    fn test_handle_command_uses_configured_owner() {
+        let mut prompter = ScriptedPrompter::new(&["my-app"]);
+        let result = handle_command(
+            &None,
+            &None,
+            &config_with_owner(Some("configured")),
+            &mut prompter,
+        );
+
+        assert_eq!(result.unwrap().owner, "configured");
+        assert_eq!(prompter.prompts, vec!["Application Name"]);
+    }
+
+    #[test]
+    fn test_handle_command_reprompts_on_invalid_input() {
+        let mut prompter = ScriptedPrompter::new(&["My App", "", "my-app", "two words", "acme"]);
+        let result = handle_command(&None, &None, &config_with_owner(None), &mut prompter);
+
+        assert!(result.is_ok());
+        assert_eq!(prompter.prompts.len(), 5);
+        assert_eq!(
+            prompter.warnings,
+            vec![
+                "Invalid application name: use only lowercase letters, digits and '-'",
+                "Invalid application name: can't be empty",
+                "Invalid owner name: the owner can't contain whitespace",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_command_non_interactive_lists_missing_values() {
+        let result = handle_command(
+            &None,
+            &None,
+            &config_with_owner(None),
+            &mut NonInteractivePrompter,
+        );
+
+        let error = result.unwrap_err();
+        assert_eq!(error.category, ErrorCategory::Usage);
+        assert_eq!(
+            error.message,
+            "Missing required values: Application Name (--app-name), Owner Name (--owner)"
+        );
+    }
+
+    #[test]
+    fn test_handle_command_rejects_invalid_argument() {
+        let mut prompter = ScriptedPrompter::new(&["ignored"]);
+        let result = handle_command(
+            &Some("-bad-".to_string()),
+            &None,
+            &config_with_owner(Some("acme")),
+            &mut prompter,
+        );
+
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid value for --app-name: can't start or end with '-'"
+        );
+        assert!(prompter.prompts.is_empty());
+    }
+
+    #[test]
+    fn test_handle_command_reports_invalid_configured_owner() {
+        let mut config = config_with_owner(Some("two words"));
+        config.origins.insert(
+            "defaults.owner".to_string(),
+            ConfigOrigin {
+                layer: ConfigLayer::User,
+                source: "/home/me/.deplio".to_string(),
+                profile: None,
+            },
+        );
+        let mut prompter = ScriptedPrompter::new(&[]);
+
+        let error =
+            handle_command(&Some("my-app".to_string()), &None, &config, &mut prompter).unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid value for defaults.owner from user (/home/me/.deplio): the owner can't contain whitespace"
+        );
+        assert_eq!(error.category, ErrorCategory::Config);
+        assert_eq!(error.exit_code(), 78);
+    }
+
+    #[test]
+    fn test_validate_owner() {
+        assert!(validate_owner("owner@example.com").is_ok());
+        assert!(validate_owner("用户名").is_ok());
+        assert!(validate_owner("").is_err());
+        assert!(validate_owner("has\ttab").is_err());
+    }
+}
@@ -1,4 +1,12 @@
+pub mod chart;
+pub mod completions;
 pub mod config;
+pub mod config_versioning;
+pub mod error;
 pub mod init;
+pub mod output;
 pub mod parser;
+pub mod project;
+pub mod prompt;
 pub mod templates;
+pub mod validation;
@@ -1,40 +1,125 @@
 use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
 use cli::{
-    config, init,
+    chart, completions,
+    config::{self, Configuration},
+    error::{CliError, Context, ErrorCategory},
+    init,
+    output::{self, OutputFormat},
     parser::{Cli, Commands},
+    prompt::{NonInteractivePrompter, Prompter, TerminalPrompter},
 };
+use lib::logging::{self, LogSettings, LoggingError};
+use std::io::IsTerminal;
+use tracing::warn;
 
 fn main() {
-    let conf = config::load_config(None).expect("unable to load configuration");
+    // answers the shell when called back from a completion script, nothing may be printed before
+    CompleteEnv::with_factory(Cli::command)
+        .var(completions::COMPLETE_VAR)
+        .complete();
+
+    // logging is set up from the command line first so loading the configuration is logged too,
+    // which means override_params can't change the verbosity
+    let parsed = Cli::try_parse();
+    let (verbose, quiet, output) = parsed
+        .as_ref()
+        .map(|cli| (cli.verbose, cli.quiet, cli.output))
+        .unwrap_or_default();
+    wrap_error(init_logging(verbose, quiet), output);
+
+    let (mut conf, load_error) = match config::load_config(None) {
+        Ok(conf) => (conf, None),
+        Err(e) => (
+            Configuration::default(),
+            Some(CliError::from(e).with_context("Unable to load configuration")),
+        ),
+    };
     let cli: Cli;
-    if conf.debug.override_params.is_some() {
-        let dev_params = shlex::split(&conf.debug.override_params.unwrap());
+    if let Some(override_params) = &conf.debug.override_params {
+        let dev_params = shlex::split(override_params);
         if let Some(params) = dev_params {
             let mut padded_params = vec![""];
             let mut casted_params: Vec<&str> = params.iter().map(AsRef::as_ref).collect();
             padded_params.append(&mut casted_params);
             cli = Cli::parse_from(padded_params);
         } else {
-            println!("illegal dev params provided");
-            cli = Cli::parse();
+            warn!("illegal dev params provided");
+            cli = parsed.unwrap_or_else(|e| e.exit());
         }
     } else {
-        cli = Cli::parse();
+        cli = parsed.unwrap_or_else(|e| e.exit());
     }
 
-    if conf.debug.synth_working_dir.is_some() {
-        std::env::set_current_dir(conf.debug.synth_working_dir.unwrap())
-            .expect("Unable to set working dir to synthetic working dir");
+    let output = cli.output;
+    if let Some(synth_working_dir) = &conf.debug.synth_working_dir {
+        wrap_error(
+            std::env::set_current_dir(synth_working_dir)
+                .map_err(|e| CliError::io(format!("{}: {}", synth_working_dir, e)))
+                .context("Unable to set working dir to synthetic working dir"),
+            output,
+        );
+    }
+
+    // config commands must keep working with a broken configuration so it can be fixed,
+    // and generating completions or man pages doesn't use it
+    if !matches!(
+        cli.command,
+        Some(Commands::Config(_) | Commands::Completions { .. } | Commands::Man { .. })
+    ) {
+        if let Some(error) = load_error {
+            wrap_error::<()>(Err(error), output);
+        }
+        wrap_error(conf.select_profile(cli.profile.as_deref()), output);
     }
 
     match &cli.command {
         Some(Commands::Init { app_name, owner }) => {
-            wrap_error(init::handle_command(app_name, owner))
+            let mut prompter: Box<dyn Prompter> =
+                if cli.non_interactive || !std::io::stdin().is_terminal() {
+                    Box::new(NonInteractivePrompter)
+                } else {
+                    Box::new(TerminalPrompter)
+                };
+            let summary = wrap_error(
+                init::handle_command(app_name, owner, &conf, prompter.as_mut()),
+                output,
+            );
+            wrap_error(output::print(output, &summary), output)
         }
-        Some(Commands::Update { version }) => {}
-        Some(Commands::Config { edit, overwrite }) => {
-            wrap_error(config::handle_command(edit, overwrite, None))
+        Some(Commands::Update { version: _ }) => {}
+        Some(Commands::Config(config_args)) => match &config_args.subcommand {
+            Some(subcommand) => wrap_error(
+                config::handle_subcommand(subcommand, None, cli.profile.as_deref(), output),
+                output,
+            ),
+            None => {
+                let result = wrap_error(
+                    config::handle_command(&config_args.edit, &config_args.overwrite, None),
+                    output,
+                );
+                wrap_error(output::print(output, &result), output)
+            }
+        },
+        Some(Commands::Chart(chart_args)) => {
+            let project_dir = wrap_error(
+                std::env::current_dir()
+                    .map_err(|e| CliError::io(format!("Unable to read working dir: {}", e))),
+                output,
+            );
+            wrap_error(
+                chart::handle_command(&chart_args.subcommand, &project_dir, output),
+                output,
+            )
         }
+        Some(Commands::Completions { shell }) => wrap_error(
+            completions::write_registration(*shell, &mut std::io::stdout()),
+            output,
+        ),
+        Some(Commands::Man { out_dir }) => wrap_error(
+            completions::write_man_pages(out_dir.as_deref(), &mut std::io::stdout()),
+            output,
+        ),
         Some(Commands::Debug(_)) => {}
         None => {
             let mut cmd = Cli::command();
@@ -43,12 +128,29 @@ fn main() {
     }
 }
 
-fn wrap_error<T>(result: Result<T, &str>) -> T {
+fn init_logging(verbose: u8, quiet: u8) -> Result<(), CliError> {
+    LogSettings::from_env(logging::verbosity_level(verbose, quiet))
+        .and_then(|settings| logging::init(&settings))
+        .map_err(|e| {
+            let error = CliError::new(ErrorCategory::Environment, e.to_string());
+            match e {
+                LoggingError::InvalidFilter(_) => error.with_suggestion(
+                    "use a level such as `debug`, or directives such as `warn,cli::config=trace`",
+                ),
+                LoggingError::InvalidFormat(_) => {
+                    error.with_suggestion("set DEPLIO_LOG_FORMAT to text or json")
+                }
+                LoggingError::InitFail(_) => error,
+            }
+        })
+}
+
+fn wrap_error<T>(result: Result<T, CliError>, output: OutputFormat) -> T {
     match result {
         Ok(value) => value,
         Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+            eprintln!("{}", output::render_error(output, &e));
+            std::process::exit(e.exit_code());
         }
     }
 }
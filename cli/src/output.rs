@@ -0,0 +1,200 @@
+use crate::error::{CliError, ErrorCategory};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// How command results and errors are printed, chosen with `--output`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// A single JSON document.
+    Json,
+    /// A single YAML document.
+    Yaml,
+}
+
+/// A command result that can be printed in every [`OutputFormat`].
+///
+/// The serialized form is the documented machine readable shape, so fields should only ever
+/// be added to it.
+pub trait Render: Serialize {
+    /// The human readable form, one entry per line.
+    fn render_text(&self) -> Vec<String>;
+}
+
+/// Renders a command result. Text without any lines renders as an empty string.
+pub fn render<T: Render>(format: OutputFormat, result: &T) -> Result<String, CliError> {
+    match format {
+        OutputFormat::Text => Ok(result
+            .render_text()
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect()),
+        OutputFormat::Json => serde_json::to_string_pretty(result)
+            .map(|json| json + "\n")
+            .map_err(|e| CliError::io(format!("Unable to render JSON output: {}", e))),
+        OutputFormat::Yaml => serde_yaml_ng::to_string(result)
+            .map_err(|e| CliError::io(format!("Unable to render YAML output: {}", e))),
+    }
+}
+
+/// Renders a command result to stdout.
+pub fn print<T: Render>(format: OutputFormat, result: &T) -> Result<(), CliError> {
+    print!("{}", render(format, result)?);
+    Ok(())
+}
+
+/// Renders an error for stderr. Text keeps the snippet and help of [`CliError`]'s `Display`.
+pub fn render_error(format: OutputFormat, error: &CliError) -> String {
+    let output = ErrorOutput {
+        error: ErrorDetails::from(error),
+    };
+    match format {
+        OutputFormat::Text => error.to_string(),
+        // serializing these plain structs can't fail, fall back to text just in case
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&output).unwrap_or_else(|_| error.to_string())
+        }
+        OutputFormat::Yaml => serde_yaml_ng::to_string(&output)
+            .map(|yaml| yaml.trim_end().to_string())
+            .unwrap_or_else(|_| error.to_string()),
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorOutput {
+    error: ErrorDetails,
+}
+
+#[derive(Serialize)]
+struct ErrorDetails {
+    category: ErrorCategory,
+    exit_code: i32,
+    message: String,
+    /// Outermost first, as in the text output.
+    context: Vec<String>,
+    location: Option<ErrorLocation>,
+    suggestion: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ErrorLocation {
+    path: PathBuf,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+impl From<&CliError> for ErrorDetails {
+    fn from(error: &CliError) -> Self {
+        ErrorDetails {
+            category: error.category,
+            exit_code: error.exit_code(),
+            message: error.message.clone(),
+            context: error.context.iter().rev().cloned().collect(),
+            location: error.source.as_ref().map(|source| {
+                let line_col = source.line_col();
+                ErrorLocation {
+                    path: source.path.clone(),
+                    line: line_col.map(|(line, _)| line),
+                    column: line_col.map(|(_, column)| column),
+                }
+            }),
+            suggestion: error.suggestion.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::TomlSourceError;
+
+    #[derive(Serialize)]
+    struct Greeting {
+        name: String,
+        tags: Vec<String>,
+    }
+
+    impl Render for Greeting {
+        fn render_text(&self) -> Vec<String> {
+            vec![format!("hello {}", self.name)]
+        }
+    }
+
+    fn greeting() -> Greeting {
+        Greeting {
+            name: "deplio".to_string(),
+            tags: vec!["cli".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            render(OutputFormat::Text, &greeting()).unwrap(),
+            "hello deplio\n"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        assert_eq!(
+            render(OutputFormat::Json, &greeting()).unwrap(),
+            "{\n  \"name\": \"deplio\",\n  \"tags\": [\n    \"cli\"\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_render_yaml() {
+        assert_eq!(
+            render(OutputFormat::Yaml, &greeting()).unwrap(),
+            "name: deplio\ntags:\n- cli\n"
+        );
+    }
+
+    #[test]
+    fn test_render_error_json() {
+        let error = CliError::toml(
+            ErrorCategory::Config,
+            TomlSourceError::new(
+                "/home/user/.deplio",
+                "[defaults]\nowner = acme\n",
+                Some(19..23),
+                "invalid string",
+            ),
+        )
+        .with_context("Unable to resolve configuration layers")
+        .with_context("Unable to load configuration")
+        .with_suggestion("quote the value");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_error(OutputFormat::Json, &error)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "error": {
+                    "category": "config",
+                    "exit_code": 78,
+                    "message": "/home/user/.deplio:2:9: invalid string",
+                    "context": [
+                        "Unable to load configuration",
+                        "Unable to resolve configuration layers"
+                    ],
+                    "location": { "path": "/home/user/.deplio", "line": 2, "column": 9 },
+                    "suggestion": "quote the value"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_error_text() {
+        let error = CliError::usage("Unknown configuration key nope");
+        assert_eq!(
+            render_error(OutputFormat::Text, &error),
+            "Error: Unknown configuration key nope"
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use crate::completions;
+use crate::output::OutputFormat;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use clap_complete::ArgValueCandidates;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "deplio")]
@@ -8,30 +12,52 @@ use clap::{Parser, Subcommand};
     project initialzation, github actions, handles updates to configuration version
 ")]
 pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "The configuration profile to use. Overrides default_profile",
+        add = ArgValueCandidates::new(completions::profile_names)
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        visible_alias = "yes",
+        global = true,
+        help = "Never prompt for input, fail listing any missing values instead"
+    )]
+    pub non_interactive: bool,
+    #[arg(
+        long,
+        value_enum,
+        global = true,
+        default_value_t = OutputFormat::Text,
+        help = "The format to print results and errors in"
+    )]
+    pub output: OutputFormat,
+    #[arg(
+        short,
+        long,
+        action = ArgAction::Count,
+        global = true,
+        help = "Log more, repeat for more detail (-v info, -vv debug, -vvv trace)"
+    )]
+    pub verbose: u8,
+    #[arg(
+        short,
+        long,
+        action = ArgAction::Count,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Log less, -q only logs errors and -qq nothing"
+    )]
+    pub quiet: u8,
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
-    #[command(
-        about,
-        long_about = "Creates the deplio configuration file for the user"
-    )]
-    Config {
-        #[arg(
-            short,
-            long,
-            help = "Open the configuration file in the default editor"
-        )]
-        edit: bool,
-        #[arg(
-            short,
-            long,
-            help = "Forces the overwrite of the config file with a new empty template"
-        )]
-        overwrite: bool,
-    },
+    Config(Config),
     #[command(
         about = "Initializes the project files for a given repository using the latest version"
     )]
@@ -39,7 +65,8 @@ pub enum Commands {
 Initializes the project files for a given repository using the latest version.
 
 This includes the project configuration and the github actions. 
-If requirements are provided with the call, or through configuration, they will be prompted for.")]
+If requirements aren't provided with the call, or through configuration, they will be prompted for.
+With --non-interactive, or when input isn't a terminal, missing requirements are reported as an error instead.")]
     Init {
         #[arg(short, long, help = "The name of the application to initialize")]
         app_name: Option<String>,
@@ -47,16 +74,154 @@ If requirements are provided with the call, or through configuration, they will
         owner: Option<String>,
     },
     Update {
+        // no short flag, -v is the global verbosity flag
         #[arg(
-            short,
             long,
             help = "The version to upgrade to. If not included 'latest' is assumed"
         )]
         version: Option<String>,
     },
+    Chart(Chart),
+    #[command(about = "Prints the script registering deplio's shell completions")]
+    #[command(long_about = "
+Prints the script registering deplio's shell completions.
+
+Completions call back into deplio, so chart and profile names are read from the current project
+and configuration. Load the script on shell startup so it stays in sync with the installed deplio:
+
+  bash:       source <(deplio completions bash)
+  zsh:        source <(deplio completions zsh)
+  fish:       deplio completions fish | source
+  powershell: deplio completions powershell | Out-String | Invoke-Expression
+  elvish:     eval (deplio completions elvish | slurp)")]
+    Completions {
+        #[arg(value_enum, help = "The shell to print the completions for")]
+        shell: CompletionShell,
+    },
+    #[command(about = "Generates the man pages of deplio")]
+    #[command(long_about = "
+Generates the man pages of deplio.
+
+Prints the page of the deplio command, or with --out-dir writes a page for every command.")]
+    Man {
+        #[arg(short, long, help = "The directory to write a page per command to")]
+        out_dir: Option<PathBuf>,
+    },
     Debug(Debug),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+#[derive(Parser)]
+#[command(
+    about = "Creates and edits the deplio configuration file for the user",
+    long_about = "Creates the deplio configuration file for the user.
+
+Without a subcommand the configuration file is created from the template if it doesn't exist yet."
+)]
+pub struct Config {
+    #[command(subcommand)]
+    pub subcommand: Option<ConfigCommands>,
+    #[arg(
+        short,
+        long,
+        help = "Open the configuration file in the default editor"
+    )]
+    pub edit: bool,
+    #[arg(
+        short,
+        long,
+        help = "Forces the overwrite of the config file with a new empty template"
+    )]
+    pub overwrite: bool,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    #[command(about = "Prints the effective value of a configuration key, e.g. defaults.owner")]
+    Get {
+        #[arg(add = ArgValueCandidates::new(completions::config_keys))]
+        key: String,
+        #[arg(
+            long,
+            help = "Print secrets such as profile tokens instead of masking them"
+        )]
+        show_secret: bool,
+    },
+    #[command(about = "Sets a configuration key in the user configuration file")]
+    Set {
+        #[arg(add = ArgValueCandidates::new(completions::config_keys))]
+        key: String,
+        value: String,
+    },
+    #[command(about = "Removes a configuration key from the user configuration file")]
+    Unset {
+        #[arg(add = ArgValueCandidates::new(completions::config_keys))]
+        key: String,
+    },
+    #[command(about = "Lists every effective configuration value")]
+    List {
+        #[arg(
+            long,
+            help = "Show the file or environment variable each value came from"
+        )]
+        show_origin: bool,
+    },
+}
+
+#[derive(Parser)]
+#[command(about = "Adds, updates, removes and lists the charts deployed with the application")]
+#[command(long_about = "
+Adds, updates, removes and lists the charts deployed with the application.
+
+Charts are edited in the project's deplio.toml, keeping its comments and layout.")]
+pub struct Chart {
+    #[command(subcommand)]
+    pub subcommand: ChartCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ChartCommands {
+    #[command(about = "Adds a chart to the application")]
+    Add {
+        #[arg(help = "The name of the chart, unique within the application")]
+        name: String,
+        #[arg(short, long, help = "The url of the chart")]
+        url: String,
+        #[arg(short, long, help = "The kubernetes namespace to deploy the chart to")]
+        namespace: String,
+    },
+    #[command(about = "Changes the url or namespace of a chart")]
+    Update {
+        #[arg(
+            help = "The name of the chart to update",
+            add = ArgValueCandidates::new(completions::chart_names)
+        )]
+        name: String,
+        #[arg(short, long, help = "The new url of the chart")]
+        url: Option<String>,
+        #[arg(short, long, help = "The new kubernetes namespace of the chart")]
+        namespace: Option<String>,
+    },
+    #[command(about = "Removes a chart from the application")]
+    Remove {
+        #[arg(
+            help = "The name of the chart to remove",
+            add = ArgValueCandidates::new(completions::chart_names)
+        )]
+        name: String,
+    },
+    #[command(about = "Lists the charts of the application")]
+    List,
+}
+
 #[derive(Parser)]
 #[command(about = "A set of debug commands useful for development on the project")]
 pub struct Debug {
@@ -0,0 +1,147 @@
+use crate::error::{CliError, ErrorCategory, TomlSourceError};
+use lib::app_config::{
+    APP_CONFIG_FILE_NAME, AppConfigV1_0_0, CURRENT_VERSION, DeserializationError,
+    deserialize_app_config,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::DocumentMut;
+use tracing::{debug, debug_span};
+
+/// The app config file of the project in a directory, as read from disk.
+#[derive(Debug)]
+pub struct AppConfigFile {
+    pub path: PathBuf,
+    pub contents: String,
+    pub config: AppConfigV1_0_0,
+}
+
+impl AppConfigFile {
+    /// Loads `deplio.toml` from `project_dir`.
+    pub fn load(project_dir: &Path) -> Result<Self, CliError> {
+        let path = project_dir.join(APP_CONFIG_FILE_NAME);
+        let _span = debug_span!("load_app_config", path = %path.display()).entered();
+        if !fs::exists(&path).unwrap_or(false) {
+            return Err(CliError::new(
+                ErrorCategory::Project,
+                format!(
+                    "No {} found in {}",
+                    APP_CONFIG_FILE_NAME,
+                    project_dir.display()
+                ),
+            )
+            .with_suggestion("run this command from the root of a deplio project"));
+        }
+        debug!("reading app config");
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| CliError::io(format!("Unable to read {}: {}", path.display(), e)))?;
+        let config = parse_app_config(&path, &contents)?;
+
+        Ok(AppConfigFile {
+            path,
+            contents,
+            config,
+        })
+    }
+
+    /// Parses the file for editing, keeping comments and layout.
+    pub fn document(&self) -> DocumentMut {
+        self.contents
+            .parse::<DocumentMut>()
+            .expect("contents were validated when loaded")
+    }
+
+    /// Writes an edited document back, after checking it's still a valid app config.
+    /// Returns the app config as written.
+    pub fn save(&self, document: &DocumentMut) -> Result<AppConfigV1_0_0, CliError> {
+        let contents = document.to_string();
+        let config = parse_app_config(&self.path, &contents)
+            .map_err(|e| e.with_context("The edited app config is invalid"))?;
+        debug!(path = %self.path.display(), "writing app config");
+        fs::write(&self.path, contents)
+            .map_err(|e| CliError::io(format!("Unable to write {}: {}", self.path.display(), e)))?;
+        Ok(config)
+    }
+}
+
+fn parse_app_config(path: &Path, contents: &str) -> Result<AppConfigV1_0_0, CliError> {
+    match deserialize_app_config(contents) {
+        Ok(config) => Ok(config),
+        Err(DeserializationError::VersionMismatch(_)) => Err(CliError::new(
+            ErrorCategory::Project,
+            format!(
+                "{} isn't a version {} app config",
+                path.display(),
+                CURRENT_VERSION
+            ),
+        )
+        .with_suggestion("run `deplio update` to upgrade the project")),
+        Err(DeserializationError::TomlError(message)) => {
+            // parse again for the location of the error
+            let source = match toml::from_str::<AppConfigV1_0_0>(contents) {
+                Err(e) => TomlSourceError::new(path, contents, e.span(), e.message()),
+                Ok(_) => TomlSourceError::new(path, contents, None, &message),
+            };
+            Err(CliError::toml(ErrorCategory::Project, source))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib::app_config::write_app_config_template;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_app_config() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let contents = write_app_config_template("my-app", "https://deplio", "acme");
+        fs::write(temp_dir.path().join(APP_CONFIG_FILE_NAME), &contents)
+            .expect("Failed to write app config");
+
+        let file = AppConfigFile::load(temp_dir.path()).expect("app config should load");
+        assert_eq!(file.config.app.name, "my-app");
+        assert_eq!(file.contents, contents);
+        assert_eq!(file.document().to_string(), contents);
+    }
+
+    #[test]
+    fn test_load_missing_app_config() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+
+        let error = AppConfigFile::load(temp_dir.path()).unwrap_err();
+        assert_eq!(error.category, ErrorCategory::Project);
+        assert!(error.message.starts_with("No deplio.toml found"));
+    }
+
+    #[test]
+    fn test_load_app_config_reports_location() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let contents = write_app_config_template("my-app", "https://deplio", "acme")
+            .replace("charts = []", "charts = 5");
+        fs::write(temp_dir.path().join(APP_CONFIG_FILE_NAME), &contents)
+            .expect("Failed to write app config");
+
+        let error = AppConfigFile::load(temp_dir.path()).unwrap_err();
+        assert_eq!(error.exit_code(), 65);
+        let source = error.source.expect("error should have a source");
+        let (line, _) = source.line_col().expect("error should have a location");
+        assert_eq!(contents.lines().nth(line - 1), Some("charts = 5"));
+    }
+
+    #[test]
+    fn test_load_app_config_version_mismatch() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let contents = write_app_config_template("my-app", "https://deplio", "acme")
+            .replace(CURRENT_VERSION, "0.0.1");
+        fs::write(temp_dir.path().join(APP_CONFIG_FILE_NAME), &contents)
+            .expect("Failed to write app config");
+
+        let error = AppConfigFile::load(temp_dir.path()).unwrap_err();
+        assert_eq!(error.category, ErrorCategory::Project);
+        assert!(error.suggestion.unwrap().contains("deplio update"));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::CliError;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+/// Asks the user for values that weren't provided on the command line or in configuration.
+pub trait Prompter {
+    /// Asks for a value. Returns `None` when no value can be asked for or the input ended.
+    fn prompt(&mut self, label: &str) -> Result<Option<String>, CliError>;
+
+    /// Tells the user why their last answer was rejected.
+    fn warn(&mut self, message: &str);
+}
+
+/// Prompts on stderr and reads answers from stdin, so stdout only holds the command's result.
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn prompt(&mut self, label: &str) -> Result<Option<String>, CliError> {
+        eprint!("{}: ", label);
+        io::stderr()
+            .flush()
+            .map_err(|e| CliError::io(format!("Unable to write prompt: {}", e)))?;
+
+        let mut answer = String::new();
+        let read = io::stdin()
+            .lock()
+            .read_line(&mut answer)
+            .map_err(|e| CliError::io(format!("Unable to read answer: {}", e)))?;
+        if read == 0 {
+            // stdin was closed, nothing more will come
+            eprintln!();
+            return Ok(None);
+        }
+        Ok(Some(answer.trim().to_string()))
+    }
+
+    fn warn(&mut self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Never prompts, so every missing value is reported instead. Used for `--non-interactive`
+/// and when stdin isn't a terminal.
+pub struct NonInteractivePrompter;
+
+impl Prompter for NonInteractivePrompter {
+    fn prompt(&mut self, _label: &str) -> Result<Option<String>, CliError> {
+        Ok(None)
+    }
+
+    fn warn(&mut self, _message: &str) {}
+}
+
+/// Answers prompts from a fixed list, recording what was asked. Meant for tests.
+#[derive(Default)]
+pub struct ScriptedPrompter {
+    answers: VecDeque<String>,
+    pub prompts: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(answers: &[&str]) -> Self {
+        ScriptedPrompter {
+            answers: answers.iter().map(|answer| answer.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn prompt(&mut self, label: &str) -> Result<Option<String>, CliError> {
+        self.prompts.push(label.to_string());
+        Ok(self.answers.pop_front())
+    }
+
+    fn warn(&mut self, message: &str) {
+        self.warnings.push(message.to_string());
+    }
+}
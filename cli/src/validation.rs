@@ -0,0 +1,44 @@
+const MAX_DNS_LABEL_LENGTH: usize = 63;
+
+/// Checks that `value` is a valid DNS label, as used for Kubernetes namespaces and names:
+/// lowercase letters, digits and '-', not starting or ending with '-', at most 63 characters.
+pub fn validate_dns_label(value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Err("can't be empty".to_string());
+    }
+    if value.len() > MAX_DNS_LABEL_LENGTH {
+        return Err(format!(
+            "can't be longer than {} characters",
+            MAX_DNS_LABEL_LENGTH
+        ));
+    }
+    if !value
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return Err("use only lowercase letters, digits and '-'".to_string());
+    }
+    if value.starts_with('-') || value.ends_with('-') {
+        return Err("can't start or end with '-'".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_dns_label() {
+        assert!(validate_dns_label("my-app-2").is_ok());
+        assert!(validate_dns_label("a").is_ok());
+        assert!(validate_dns_label(&"a".repeat(63)).is_ok());
+        assert!(validate_dns_label(&"a".repeat(64)).is_err());
+        assert!(validate_dns_label("").is_err());
+        assert!(validate_dns_label("my_app").is_err());
+        assert!(validate_dns_label("My-App").is_err());
+        assert!(validate_dns_label("-app").is_err());
+        assert!(validate_dns_label("app-").is_err());
+        assert!(validate_dns_label("测试应用").is_err());
+    }
+}
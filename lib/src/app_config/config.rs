@@ -6,6 +6,9 @@ use toml;
 
 pub static CURRENT_VERSION: &str = "1.0.0";
 
+/// The name of the app config file at the root of a project.
+pub static APP_CONFIG_FILE_NAME: &str = "deplio.toml";
+
 /// Represents the error that can occur during deserialization of the app config.
 #[derive(Debug, Error)]
 pub enum DeserializationError {
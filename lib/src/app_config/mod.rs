@@ -1,6 +1,5 @@
 mod config;
 mod templates;
-mod traits;
 mod v1_models;
 mod versioning;
 
@@ -9,6 +8,10 @@ mod config_tests;
 #[cfg(test)]
 mod versioning_tests;
 
-pub use self::config::{CURRENT_VERSION, deserialize_app_config, write_app_config_template};
+pub use self::config::{
+    APP_CONFIG_FILE_NAME, CURRENT_VERSION, DeserializationError, deserialize_app_config,
+    write_app_config_template,
+};
 pub use self::v1_models::*;
-pub use self::versioning::{UpgradeError, upgrade_data};
+pub use self::versioning::upgrade_data;
+pub use crate::versioning::{UpgradeError, Upgrader};
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use super::super::traits::Upgrader;
+    use super::super::Upgrader;
     use super::super::versioning::upgrade_data;
 
     // Mock upgrader for testing
@@ -41,7 +41,7 @@ mod tests {
         let result = upgrade_data("invalid-version", "1.0.0", "test-data", None);
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::InvalidVersionFormat(msg) => {
+            super::super::UpgradeError::InvalidVersionFormat(msg) => {
                 assert!(msg.contains("Invalid from_version"));
             }
             _ => panic!("Expected InvalidVersionFormat error"),
@@ -53,7 +53,7 @@ mod tests {
         let result = upgrade_data("1.0.0", "invalid-version", "test-data", None);
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::InvalidVersionFormat(msg) => {
+            super::super::UpgradeError::InvalidVersionFormat(msg) => {
                 assert!(msg.contains("Invalid to_version"));
             }
             _ => panic!("Expected InvalidVersionFormat error"),
@@ -65,7 +65,7 @@ mod tests {
         let result = upgrade_data("2.0.0", "1.0.0", "test-data", None);
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::DowngradeNotSupported { from, to } => {
+            super::super::UpgradeError::DowngradeNotSupported { from, to } => {
                 assert_eq!(from, "2.0.0");
                 assert_eq!(to, "1.0.0");
             }
@@ -78,7 +78,7 @@ mod tests {
         let result = upgrade_data("1.0.0", "90000.0.0", "test-data", None);
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::UnsupportedTargetVersion(version) => {
+            super::super::UpgradeError::UnsupportedTargetVersion(version) => {
                 assert_eq!(version, "90000.0.0");
             }
             _ => panic!("Expected UnsupportedTargetVersion error"),
@@ -105,7 +105,7 @@ mod tests {
         let result = upgrade_data("v1.0.0", "1.0.0", "test-data", None);
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::InvalidVersionFormat(msg) => {
+            super::super::UpgradeError::InvalidVersionFormat(msg) => {
                 assert!(msg.contains("Invalid from_version"));
             }
             _ => panic!("Expected InvalidVersionFormat error"),
@@ -117,7 +117,7 @@ mod tests {
         let result = upgrade_data(" 1.0.0 ", "1.0.0", "test-data", None);
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::InvalidVersionFormat(msg) => {
+            super::super::UpgradeError::InvalidVersionFormat(msg) => {
                 assert!(msg.contains("Invalid from_version"));
             }
             _ => panic!("Expected InvalidVersionFormat error"),
@@ -187,7 +187,7 @@ mod tests {
         let result = upgrade_data("1.0.0", "2.0.0", "test-data", Some(upgraders));
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::BreakingChange { from, to, message } => {
+            super::super::UpgradeError::BreakingChange { from, to, message } => {
                 assert_eq!(from, "1.0.0");
                 assert_eq!(to, "2.0.0");
                 assert_eq!(message, "This is a breaking change");
@@ -232,7 +232,7 @@ mod tests {
         assert!(result.is_err());
         // Should fail since no upgrader can handle 1.0.0 to reach target 1.1.0
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::NoRouteFound(version) => {
+            super::super::UpgradeError::NoRouteFound(version) => {
                 assert_eq!(version, "1.1.0");
             }
             _ => panic!("Expected NoRouteFound error"),
@@ -253,7 +253,7 @@ mod tests {
         let result = upgrade_data("1.0.0", "1.2.0", "test-data", Some(upgraders));
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::UnsupportedTargetVersion(version) => {
+            super::super::UpgradeError::UnsupportedTargetVersion(version) => {
                 assert_eq!(version, "1.2.0");
             }
             _ => panic!("Expected UnsupportedTargetVersion error"),
@@ -267,7 +267,7 @@ mod tests {
         let result = upgrade_data("1.0.0", "1.1.0", "test-data", Some(upgraders));
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::UnsupportedTargetVersion(version) => {
+            super::super::UpgradeError::UnsupportedTargetVersion(version) => {
                 assert_eq!(version, "1.1.0");
             }
             _ => panic!("Expected UnsupportedTargetVersion error"),
@@ -399,7 +399,7 @@ mod tests {
         let result = upgrade_data("1.0.0", "1.3.0", "data", Some(upgraders));
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::NoRouteFound(version) => {
+            super::super::UpgradeError::NoRouteFound(version) => {
                 assert_eq!(version, "1.3.0");
             }
             _ => panic!("Expected NoRouteFound error"),
@@ -455,7 +455,7 @@ mod tests {
         let result = upgrade_data("1.0.0", "1.3.0", "data", Some(upgraders));
         assert!(result.is_err());
         match result.unwrap_err() {
-            super::super::versioning::UpgradeError::NoRouteFound(version) => {
+            super::super::UpgradeError::NoRouteFound(version) => {
                 assert_eq!(version, "1.3.0");
             }
             _ => panic!("Expected NoRouteFound error"),
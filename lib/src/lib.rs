@@ -1 +1,3 @@
 pub mod app_config;
+pub mod logging;
+pub mod versioning;
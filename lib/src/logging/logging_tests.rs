@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{LogFormat, LogSettings, LoggingError, verbosity_level};
+    use tracing_subscriber::filter::LevelFilter;
+
+    fn settings(filter: Option<&str>) -> LogSettings {
+        LogSettings {
+            default_level: LevelFilter::WARN,
+            filter: filter.map(str::to_string),
+            format: LogFormat::Text,
+        }
+    }
+
+    #[test]
+    fn test_verbosity_level() {
+        assert_eq!(verbosity_level(0, 0), LevelFilter::WARN);
+        assert_eq!(verbosity_level(1, 0), LevelFilter::INFO);
+        assert_eq!(verbosity_level(2, 0), LevelFilter::DEBUG);
+        assert_eq!(verbosity_level(5, 0), LevelFilter::TRACE);
+        assert_eq!(verbosity_level(0, 1), LevelFilter::ERROR);
+        assert_eq!(verbosity_level(0, 3), LevelFilter::OFF);
+    }
+
+    #[test]
+    fn test_log_format_from_str() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("TEXT".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!(matches!(
+            "xml".parse::<LogFormat>(),
+            Err(LoggingError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_env_filter_uses_default_level() {
+        let filter = settings(None).env_filter().unwrap();
+        assert_eq!(filter.to_string(), "warn");
+    }
+
+    #[test]
+    fn test_env_filter_adds_directives() {
+        let filter = settings(Some("cli::config=trace")).env_filter().unwrap();
+        assert_eq!(filter.max_level_hint(), Some(LevelFilter::TRACE));
+        assert!(filter.to_string().contains("cli::config=trace"));
+    }
+
+    #[test]
+    fn test_env_filter_rejects_invalid_directives() {
+        let error = settings(Some("cli=loud")).env_filter().unwrap_err();
+        assert!(matches!(error, LoggingError::InvalidFilter(_)));
+        assert!(error.to_string().starts_with("Invalid DEPLIO_LOG filter"));
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod logging_tests;
+
+use std::{env, io::IsTerminal, str::FromStr};
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+
+pub use tracing_subscriber::filter::LevelFilter;
+
+/// Filter directives, e.g. `debug` or `warn,cli::config=trace`. Takes precedence over the
+/// level a binary defaults to, including the CLI's `-v` and `-q` flags.
+pub const LOG_FILTER_VAR: &str = "DEPLIO_LOG";
+/// `text` or `json`. Defaults to `text`.
+pub const LOG_FORMAT_VAR: &str = "DEPLIO_LOG_FORMAT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per event, including the fields of the spans it happened in.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = LoggingError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(LoggingError::InvalidFormat(value.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("Invalid {LOG_FILTER_VAR} filter: {0}")]
+    InvalidFilter(String),
+    #[error("Invalid {LOG_FORMAT_VAR} value {0}, expected text or json")]
+    InvalidFormat(String),
+    #[error("Unable to initialize logging: {0}")]
+    InitFail(String),
+}
+
+/// The level to log at when `DEPLIO_LOG` isn't set: `warn`, raised one level by each
+/// `verbose` and lowered one level by each `quiet`.
+pub fn verbosity_level(verbose: u8, quiet: u8) -> LevelFilter {
+    const LEVELS: [LevelFilter; 6] = [
+        LevelFilter::OFF,
+        LevelFilter::ERROR,
+        LevelFilter::WARN,
+        LevelFilter::INFO,
+        LevelFilter::DEBUG,
+        LevelFilter::TRACE,
+    ];
+    let index = (2 + i32::from(verbose) - i32::from(quiet)).clamp(0, LEVELS.len() as i32 - 1);
+    LEVELS[index as usize]
+}
+
+/// How a binary logs, shared by the CLI and the service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogSettings {
+    pub default_level: LevelFilter,
+    /// Filter directives overriding `default_level`, usually from `DEPLIO_LOG`.
+    pub filter: Option<String>,
+    pub format: LogFormat,
+}
+
+impl LogSettings {
+    /// Reads `DEPLIO_LOG` and `DEPLIO_LOG_FORMAT`, logging at `default_level` otherwise.
+    pub fn from_env(default_level: LevelFilter) -> Result<Self, LoggingError> {
+        let format = match env::var(LOG_FORMAT_VAR) {
+            Ok(format) if !format.is_empty() => format.parse()?,
+            _ => LogFormat::default(),
+        };
+        Ok(LogSettings {
+            default_level,
+            filter: env::var(LOG_FILTER_VAR).ok().filter(|f| !f.is_empty()),
+            format,
+        })
+    }
+
+    /// Builds the filter: `default_level` for everything, refined by the filter directives.
+    pub fn env_filter(&self) -> Result<EnvFilter, LoggingError> {
+        EnvFilter::builder()
+            .with_default_directive(self.default_level.into())
+            .parse(self.filter.as_deref().unwrap_or_default())
+            .map_err(|e| LoggingError::InvalidFilter(e.to_string()))
+    }
+}
+
+/// Installs the global subscriber. Logs go to stderr, leaving stdout to command output.
+pub fn init(settings: &LogSettings) -> Result<(), LoggingError> {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(settings.env_filter()?)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal());
+    match settings.format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().with_current_span(true).try_init(),
+    }
+    .map_err(|e| LoggingError::InitFail(e.to_string()))
+}